@@ -1,6 +1,8 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use rand::thread_rng;
-use yggdrasil_keys::NodeIdentity;
+use yggdrasil_keys::{
+    address_for_key, bits, strength_of_public_key, Miner, NodeIdentity, Prefix, ReplayRng,
+};
 
 fn generate_keys(c: &mut Criterion) {
     let mut rng = thread_rng();
@@ -13,5 +15,87 @@ fn generate_keys(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, generate_keys);
+fn derive_addresses(c: &mut Criterion) {
+    let node = NodeIdentity::new(&mut thread_rng());
+    c.bench_function("derive_address", |b| {
+        b.iter(|| {
+            black_box(black_box(&node).address());
+        })
+    });
+    c.bench_function("derive_subnet", |b| {
+        b.iter(|| {
+            black_box(black_box(&node).subnet());
+        })
+    });
+    let public = node.to_public();
+    c.bench_function("derive_address_public", |b| {
+        b.iter(|| {
+            black_box(black_box(&public).address());
+        })
+    });
+    let public_key = *node.public_key_bytes();
+    c.bench_function("derive_address_raw", |b| {
+        b.iter(|| {
+            black_box(address_for_key(black_box(&public_key), &Prefix::YGGDRASIL));
+        })
+    });
+    c.bench_function("strength", |b| {
+        b.iter(|| {
+            black_box(black_box(&node).strength());
+        })
+    });
+    // Mined keys start with many zeroes, which the byte-wise reference has to loop over
+    let mut mined = [0x5a; 32];
+    mined[..12].fill(0);
+    c.bench_function("strength_mined", |b| {
+        b.iter(|| {
+            black_box(strength_of_public_key(black_box(&mined)));
+        })
+    });
+    c.bench_function("strength_mined_bytewise", |b| {
+        b.iter(|| {
+            let mut inverted = *black_box(&mined);
+            bits::invert(&mut inverted);
+            black_box(bits::leading_ones(&inverted));
+        })
+    });
+}
+
+fn derive_many_addresses(c: &mut Criterion) {
+    let keys: Vec<[u8; 32]> = (0..1000)
+        .map(|_| *NodeIdentity::new(&mut thread_rng()).public_key_bytes())
+        .collect();
+    c.bench_function("derive_1000_addresses_loop", |b| {
+        b.iter(|| {
+            for key in black_box(&keys) {
+                black_box(address_for_key(key, &Prefix::YGGDRASIL));
+            }
+        })
+    });
+    c.bench_function("derive_1000_addresses_batch", |b| {
+        b.iter(|| {
+            for address in yggdrasil_keys::derive_addresses(black_box(&keys), &Prefix::YGGDRASIL) {
+                black_box(address);
+            }
+        })
+    });
+}
+
+fn mine_replayed(c: &mut Criterion) {
+    // The same seed makes every run go through the same 1000 keys
+    c.bench_function("mine_1000_replayed", |b| {
+        b.iter(|| {
+            let mut miner = Miner::new(u32::MAX);
+            black_box(miner.mine(&mut ReplayRng::new([0; 32]), 1000));
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    generate_keys,
+    derive_addresses,
+    derive_many_addresses,
+    mine_replayed
+);
 criterion_main!(benches);
@@ -0,0 +1,81 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+use serde_with::{DeserializeAs, SerializeAs};
+use zeroize::Zeroizing;
+
+use crate::{helper::public_hex_to_bytes, NodeIdentity};
+
+/// `serde_with` adapter for ed25519 secret keys stored as `[u8; 32]`.
+///
+/// Keys are serialized like the `PrivateKey` in yggdrasil-go's configuration, as the hex encoded
+/// secret key followed by the public key. When deserializing, the public key may be omitted,
+/// but if it's present, it has to belong to the secret key.
+///
+/// ```rust
+/// use serde::{Deserialize, Serialize};
+/// use serde_with::As;
+/// use yggdrasil_keys::{YggPublicHex, YggSecretHex};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     #[serde(with = "As::<YggSecretHex>")]
+///     private_key: [u8; 32],
+///     #[serde(with = "As::<Vec<YggPublicHex>>")]
+///     allowed_public_keys: Vec<[u8; 32]>,
+/// }
+/// ```
+pub struct YggSecretHex;
+
+impl SerializeAs<[u8; 32]> for YggSecretHex {
+    fn serialize_as<S: Serializer>(source: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        let identity = NodeIdentity {
+            signing_keys: ed25519_dalek::SigningKey::from_bytes(source),
+        };
+        serializer.serialize_str(&Zeroizing::new(identity.hex_joined()))
+    }
+}
+
+impl<'de> DeserializeAs<'de, [u8; 32]> for YggSecretHex {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        let string = Zeroizing::new(String::deserialize(deserializer)?);
+        let identity = NodeIdentity::from_hex(&string, None).map_err(D::Error::custom)?;
+        Ok(identity.signing_keys.to_bytes())
+    }
+}
+
+/// `serde_with` adapter for ed25519 public keys stored as `[u8; 32]`,
+/// serialized as hex like the `PublicKey` in yggdrasil-go's configuration.
+///
+/// See [`YggSecretHex`] for an example.
+pub struct YggPublicHex;
+
+impl SerializeAs<[u8; 32]> for YggPublicHex {
+    fn serialize_as<S: Serializer>(source: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(source))
+    }
+}
+
+impl<'de> DeserializeAs<'de, [u8; 32]> for YggPublicHex {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        let string = String::deserialize(deserializer)?;
+        public_hex_to_bytes(&string).map_err(D::Error::custom)
+    }
+}
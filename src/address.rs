@@ -0,0 +1,776 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use core::convert::TryFrom;
+use core::fmt;
+use core::net::Ipv6Addr;
+use core::str::FromStr;
+
+#[cfg(feature = "ipnet")]
+use ipnet::Ipv6Net;
+#[cfg(feature = "ipnet")]
+use sha2::{Digest, Sha512};
+
+use crate::{
+    helper::{address_bytes, inverted_leading_ones, strip_inverted_words},
+    AddressInfoError, PrefixError,
+};
+
+/// The longest prefix addresses can be derived with
+const MAX_PREFIX_LEN: usize = 14;
+/// The longest prefix subnets can be derived with
+const MAX_SUBNET_PREFIX_LEN: usize = 6;
+
+/// An IP prefix for deriving addresses and subnets, like `200::/7` for the yggdrasil network.
+///
+/// The prefix consists of whole bytes, the lowest bit of the last byte is replaced by a flag
+/// telling addresses and subnets apart. Written as CIDR, prefixes therefore are one bit shorter
+/// than a multiple of eight, like `/7` or `/15`, and have to be between 1 and 14 bytes long.
+///
+/// ```rust
+/// use yggdrasil_keys::Prefix;
+///
+/// let prefix = Prefix::from_cidr("200::/7").unwrap();
+/// assert_eq!(prefix, Prefix::YGGDRASIL);
+/// assert_eq!(prefix.as_bytes(), &[0x02]);
+/// assert_eq!(prefix.to_string(), "200::/7");
+/// assert!(Prefix::from_cidr("200::/8").is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Prefix {
+    bytes: [u8; MAX_PREFIX_LEN],
+    len: u8,
+}
+
+impl Prefix {
+    /// The prefix of the yggdrasil network, `200::/7`
+    pub const YGGDRASIL: Prefix = Prefix {
+        bytes: [0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        len: 1,
+    };
+
+    /// Create a prefix from 1 to 14 bytes. The lowest bit of the last byte is ignored.
+    pub fn new(prefix: &[u8]) -> Result<Self, PrefixError> {
+        match prefix.len() {
+            0 => return Err(PrefixError::Empty),
+            length if length > MAX_PREFIX_LEN => {
+                return Err(PrefixError::TooLong {
+                    length,
+                    max: MAX_PREFIX_LEN,
+                })
+            }
+            _ => {}
+        }
+        let mut bytes = [0u8; MAX_PREFIX_LEN];
+        bytes[..prefix.len()].copy_from_slice(prefix);
+        bytes[prefix.len() - 1] &= 0xfe;
+        Ok(Self {
+            bytes,
+            len: prefix.len() as u8,
+        })
+    }
+
+    /// Parse a prefix in CIDR notation, like `200::/7`.
+    ///
+    /// The length has to be one bit short of a multiple of eight, and no bits may be set after
+    /// the prefix.
+    pub fn from_cidr(cidr: &str) -> Result<Self, PrefixError> {
+        let (address, length) = cidr.split_once('/').ok_or(PrefixError::InvalidCidr)?;
+        let address: Ipv6Addr = address.parse().map_err(|_| PrefixError::InvalidCidr)?;
+        let length: u8 = length.parse().map_err(|_| PrefixError::InvalidCidr)?;
+        if length > 127 || length % 8 != 7 {
+            return Err(PrefixError::UnalignedLength(length));
+        }
+        let bits = u128::from(address);
+        if bits & (u128::MAX >> length) != 0 {
+            return Err(PrefixError::HostBitsSet);
+        }
+        Self::new(&address.octets()[..usize::from(length / 8 + 1)])
+    }
+
+    /// The bytes of the prefix, including the ignored lowest bit of the last byte
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..usize::from(self.len)]
+    }
+}
+
+impl Default for Prefix {
+    fn default() -> Self {
+        Self::YGGDRASIL
+    }
+}
+
+impl AsRef<[u8]> for Prefix {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+/// Parses prefixes in CIDR notation, see [`Prefix::from_cidr`].
+impl FromStr for Prefix {
+    type Err = PrefixError;
+
+    fn from_str(cidr: &str) -> Result<Self, PrefixError> {
+        Self::from_cidr(cidr)
+    }
+}
+
+/// Shows the prefix in CIDR notation.
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut octets = [0u8; 16];
+        octets[..MAX_PREFIX_LEN].copy_from_slice(&self.bytes);
+        write!(f, "{}/{}", Ipv6Addr::from(octets), self.len * 8 - 1)
+    }
+}
+
+/// A yggdrasil network, identified by the prefix its addresses and subnets are derived with.
+///
+/// Private deployments can use their own prefix, so their addresses don't collide with the public
+/// network:
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{Network, NodeIdentity};
+///
+/// let testnet = Network::new("testnet", "fc00::/7".parse().unwrap()).unwrap();
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// assert!(testnet.contains(node.address_on(&testnet)));
+/// assert!(!Network::YGGDRASIL.contains(node.address_on(&testnet)));
+/// assert_eq!(node.address_on(&Network::YGGDRASIL), node.address());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Network {
+    name: &'static str,
+    prefix: Prefix,
+}
+
+impl Network {
+    /// The public yggdrasil network, using `200::/7`
+    pub const YGGDRASIL: Network = Network {
+        name: "yggdrasil",
+        prefix: Prefix::YGGDRASIL,
+    };
+
+    /// Define a network. The prefix has to be at most 6 bytes long, so subnets can be derived.
+    pub fn new(name: &'static str, prefix: Prefix) -> Result<Self, PrefixError> {
+        let length = prefix.as_bytes().len();
+        if length > MAX_SUBNET_PREFIX_LEN {
+            return Err(PrefixError::TooLong {
+                length,
+                max: MAX_SUBNET_PREFIX_LEN,
+            });
+        }
+        Ok(Self { name, prefix })
+    }
+
+    /// The name of the network
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The prefix addresses and subnets in this network are derived with
+    pub fn prefix(&self) -> Prefix {
+        self.prefix
+    }
+
+    /// Whether an address lies within this network, either as node address or within a subnet.
+    pub fn contains(&self, address: Ipv6Addr) -> bool {
+        self.classify(address).is_some()
+    }
+
+    /// Tell whether an address is a node address or within a subnet of this network, or neither.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::{AddressKind, Network, NodeIdentity};
+    ///
+    /// let node = NodeIdentity::new(&mut thread_rng());
+    /// let network = Network::YGGDRASIL;
+    /// assert_eq!(network.classify(node.address()), Some(AddressKind::Node));
+    /// assert_eq!(network.classify(node.address_in_subnet(1)), Some(AddressKind::Subnet));
+    /// assert_eq!(network.classify("fd00::1".parse().unwrap()), None);
+    /// ```
+    pub fn classify(&self, address: Ipv6Addr) -> Option<AddressKind> {
+        let prefix = self.prefix.as_bytes();
+        let (last, rest) = prefix.split_last().unwrap();
+        let octets = address.octets();
+        if octets[..rest.len()] != *rest || octets[rest.len()] & 0xfe != *last {
+            return None;
+        }
+        if octets[rest.len()] & 0x01 == 0 {
+            Some(AddressKind::Node)
+        } else {
+            Some(AddressKind::Subnet)
+        }
+    }
+
+    /// Tell whether a network lies entirely within the node addresses or the subnets of this
+    /// network, or neither.
+    #[cfg(feature = "ipnet")]
+    pub fn classify_net(&self, net: Ipv6Net) -> Option<AddressKind> {
+        if usize::from(net.prefix_len()) < self.prefix.as_bytes().len() * 8 {
+            return None;
+        }
+        self.classify(net.network())
+    }
+}
+
+/// What an address within a [`Network`] is used for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AddressKind {
+    /// The address of a node, derived from its key
+    Node,
+    /// An address within the routed `/64` subnet of a node
+    Subnet,
+}
+
+/// Whether an address is the address of a node in the public yggdrasil network, i.e. within
+/// `200::/8`.
+pub fn is_yggdrasil_address(address: Ipv6Addr) -> bool {
+    Network::YGGDRASIL.classify(address) == Some(AddressKind::Node)
+}
+
+/// Whether a network lies within the subnets of the public yggdrasil network, i.e. within
+/// `300::/8`.
+#[cfg(feature = "ipnet")]
+pub fn is_yggdrasil_subnet(subnet: Ipv6Net) -> bool {
+    Network::YGGDRASIL.classify_net(subnet) == Some(AddressKind::Subnet)
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self::YGGDRASIL
+    }
+}
+
+/// Shows the name, followed by the prefix in parentheses.
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.prefix)
+    }
+}
+
+/// Calculate the strength of a raw public key, without constructing an identity.
+///
+/// This is the same as [`PublicNodeIdentity::strength`](crate::PublicNodeIdentity::strength).
+pub fn strength_of_public_key(public_key: &[u8; 32]) -> u32 {
+    inverted_leading_ones(public_key)
+}
+
+/// Calculate the address for a raw public key within the given prefix, without constructing an
+/// identity.
+///
+/// ```rust
+/// use yggdrasil_keys::{address_for_key, subnet_for_key, Prefix, PublicNodeIdentity};
+///
+/// let peer = PublicNodeIdentity::from_hex(
+///     "00000305eb7f19cb4506f937494ea2ebcf58e346604c0cf76be5f67271fd9a97",
+/// )
+/// .unwrap();
+/// assert_eq!(address_for_key(peer.as_bytes(), &Prefix::YGGDRASIL), peer.address());
+/// assert_eq!(subnet_for_key(peer.as_bytes(), &Prefix::YGGDRASIL), Ok(peer.subnet()));
+/// ```
+pub fn address_for_key(public_key: &[u8; 32], prefix: &Prefix) -> Ipv6Addr {
+    Ipv6Addr::from(address_bytes(public_key, prefix.as_bytes(), false))
+}
+
+/// Calculate the addresses for many raw public keys within the given prefix.
+///
+/// The prefix is prepared once, and each address is assembled from a few wide integer operations,
+/// so this is faster than calling [`address_for_key`] for each key. The addresses are yielded in
+/// the order of the keys.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use std::net::Ipv6Addr;
+/// use yggdrasil_keys::{derive_addresses, NodeIdentity, Prefix};
+///
+/// let nodes: Vec<_> = (0..3).map(|_| NodeIdentity::new(&mut thread_rng())).collect();
+/// let keys: Vec<[u8; 32]> = nodes.iter().map(|node| *node.public_key_bytes()).collect();
+/// let addresses: Vec<Ipv6Addr> = derive_addresses(&keys, &Prefix::YGGDRASIL).collect();
+/// assert_eq!(addresses[1], nodes[1].address());
+/// ```
+pub fn derive_addresses<'a, I>(keys: I, prefix: &Prefix) -> DeriveAddresses<I::IntoIter>
+where
+    I: IntoIterator<Item = &'a [u8; 32]>,
+{
+    let mut template = [0u8; 16];
+    template[..prefix.as_bytes().len()].copy_from_slice(prefix.as_bytes());
+    DeriveAddresses {
+        keys: keys.into_iter(),
+        template: u128::from_be_bytes(template),
+        shift: 120 - 8 * prefix.as_bytes().len() as u32,
+    }
+}
+
+/// Iterator over the addresses of raw public keys, see [`derive_addresses`].
+#[derive(Clone, Debug)]
+pub struct DeriveAddresses<I> {
+    keys: I,
+    /// The prefix, with the flag bit cleared
+    template: u128,
+    /// The position of the strength byte
+    shift: u32,
+}
+
+impl<'a, I: Iterator<Item = &'a [u8; 32]>> Iterator for DeriveAddresses<I> {
+    type Item = Ipv6Addr;
+
+    fn next(&mut self) -> Option<Ipv6Addr> {
+        let (ones, high, _) = strip_inverted_words(self.keys.next()?);
+        // The strength byte follows the prefix, the remaining bits fill up the address
+        let address =
+            self.template | (u128::from(ones as u8) << self.shift) | (high >> (128 - self.shift));
+        Some(Ipv6Addr::from(address))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl<'a, I: ExactSizeIterator<Item = &'a [u8; 32]>> ExactSizeIterator for DeriveAddresses<I> {}
+
+/// Calculate the `/64` subnet for a raw public key within the given prefix, without constructing
+/// an identity. Fails for prefixes longer than 6 bytes.
+#[cfg(feature = "ipnet")]
+pub fn subnet_for_key(public_key: &[u8; 32], prefix: &Prefix) -> Result<Ipv6Net, PrefixError> {
+    let length = prefix.as_bytes().len();
+    if length > MAX_SUBNET_PREFIX_LEN {
+        return Err(PrefixError::TooLong {
+            length,
+            max: MAX_SUBNET_PREFIX_LEN,
+        });
+    }
+    let address = Ipv6Addr::from(address_bytes(public_key, prefix.as_bytes(), true));
+    Ok(Ipv6Net::new(address, 64).unwrap().trunc())
+}
+
+/// Check whether an address was derived from a raw public key, with the default IP prefix.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{does_address_match_key, NodeIdentity};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let other = NodeIdentity::new(&mut thread_rng());
+/// assert!(does_address_match_key(node.address(), node.public_key_bytes()));
+/// assert!(!does_address_match_key(node.address(), other.public_key_bytes()));
+/// ```
+pub fn does_address_match_key(address: Ipv6Addr, public_key: &[u8; 32]) -> bool {
+    address == address_for_key(public_key, &Prefix::YGGDRASIL)
+}
+
+/// Check whether a `/64` subnet was derived from a raw public key, with the default IP prefix.
+///
+/// Subnets with a different prefix length never match, host bits are ignored.
+#[cfg(feature = "ipnet")]
+pub fn does_subnet_match_key(subnet: Ipv6Net, public_key: &[u8; 32]) -> bool {
+    subnet_for_key(public_key, &Prefix::YGGDRASIL) == Ok(subnet.trunc())
+}
+
+/// Calculate the `/64` subnet of the node owning an address in the public yggdrasil network,
+/// without knowing its key.
+///
+/// Returns `None` for addresses that aren't node addresses.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{address_prefix_for_subnet, subnet_for_address, NodeIdentity};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// assert_eq!(subnet_for_address(node.address()), Some(node.subnet()));
+/// let prefix = address_prefix_for_subnet(node.subnet()).unwrap();
+/// assert!(prefix.contains(&node.address()));
+/// ```
+#[cfg(feature = "ipnet")]
+pub fn subnet_for_address(address: Ipv6Addr) -> Option<Ipv6Net> {
+    if !is_yggdrasil_address(address) {
+        return None;
+    }
+    let mut octets = address.octets();
+    octets[0] |= 0x01;
+    Some(Ipv6Net::new(Ipv6Addr::from(octets), 64).unwrap().trunc())
+}
+
+/// Calculate the `/64` containing the address of the node owning a subnet in the public
+/// yggdrasil network. Only the upper 64 bits of the address are known from the subnet.
+///
+/// Returns `None` for networks that aren't within the `/64` subnet of a node.
+#[cfg(feature = "ipnet")]
+pub fn address_prefix_for_subnet(subnet: Ipv6Net) -> Option<Ipv6Net> {
+    if subnet.prefix_len() < 64 || !is_yggdrasil_subnet(subnet) {
+        return None;
+    }
+    let mut octets = subnet.network().octets();
+    octets[0] &= 0xfe;
+    Some(Ipv6Net::new(Ipv6Addr::from(octets), 64).unwrap().trunc())
+}
+
+/// What can be recovered about the public key an address or subnet was derived from.
+///
+/// Addresses contain the strength of the key, followed by the bits of the key after its leading
+/// zeroes and the one ending them, up to the end of the address. That's enough to tell which keys
+/// could own an address, but not to recover the full key.
+///
+/// ```rust
+/// use core::convert::TryFrom;
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{AddressInfo, Network, NodeIdentity};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let info = AddressInfo::try_from(node.address()).unwrap();
+/// assert_eq!(info.network(), Network::YGGDRASIL);
+/// assert_eq!(info.strength(), node.strength());
+/// assert!(!info.is_subnet());
+/// let known = info.known_bits() as usize / 8;
+/// assert_eq!(info.key_bits()[..known], node.public_key_bytes()[..known]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AddressInfo {
+    network: Network,
+    is_subnet: bool,
+    strength: u32,
+    key_bits: [u8; 32],
+    known_bits: u32,
+}
+
+impl AddressInfo {
+    /// Recover the key information from an address or subnet address in the given network.
+    pub fn with_network(address: Ipv6Addr, network: &Network) -> Result<Self, AddressInfoError> {
+        if !network.contains(address) {
+            return Err(AddressInfoError::OutsideNetwork);
+        }
+        let octets = address.octets();
+        let prefix_len = network.prefix().as_bytes().len();
+        let is_subnet = octets[prefix_len - 1] & 0x01 == 0x01;
+        let strength = u32::from(octets[prefix_len]);
+        let end = if is_subnet { 8 } else { 16 };
+
+        // The leading zeroes are already in place, followed by a one
+        // and the remaining bits, which are stored inverted.
+        let mut key_bits = [0u8; 32];
+        let mut known_bits = strength;
+        let mut push = |bit: bool| {
+            if known_bits < 256 {
+                if bit {
+                    key_bits[(known_bits / 8) as usize] |= 0x80 >> (known_bits % 8);
+                }
+                known_bits += 1;
+            }
+        };
+        push(true);
+        for byte in &octets[(prefix_len + 1)..end] {
+            for shift in (0..8).rev() {
+                push((byte >> shift) & 0x01 == 0);
+            }
+        }
+
+        Ok(Self {
+            network: *network,
+            is_subnet,
+            strength,
+            key_bits,
+            known_bits,
+        })
+    }
+
+    /// The network the address is in
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Whether this is the address of a subnet rather than of a node
+    pub fn is_subnet(&self) -> bool {
+        self.is_subnet
+    }
+
+    /// The strength of the key, the number of its leading zero bits
+    pub fn strength(&self) -> u32 {
+        self.strength
+    }
+
+    /// The start of the public key, valid for the first [`AddressInfo::known_bits`] bits. The
+    /// remaining bits are zero.
+    pub fn key_bits(&self) -> &[u8; 32] {
+        &self.key_bits
+    }
+
+    /// The number of leading bits of the public key known from the address
+    pub fn known_bits(&self) -> u32 {
+        self.known_bits
+    }
+}
+
+/// Recovers the key information from addresses in the public yggdrasil network.
+impl TryFrom<Ipv6Addr> for AddressInfo {
+    type Error = AddressInfoError;
+
+    fn try_from(address: Ipv6Addr) -> Result<Self, AddressInfoError> {
+        Self::with_network(address, &Network::YGGDRASIL)
+    }
+}
+
+/// What can be recovered about the public key a `/64` subnet was derived from, see
+/// [`AddressInfo`]. Subnets contain fewer bits of the key than addresses.
+///
+/// ```rust
+/// use core::convert::TryFrom;
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{NodeIdentity, PartialKeyMatcher, SubnetInfo};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let info = SubnetInfo::try_from(node.subnet()).unwrap();
+/// assert_eq!(info.strength(), node.strength());
+/// assert!(PartialKeyMatcher::from(info).matches(node.public_key_bytes()));
+/// ```
+#[cfg(feature = "ipnet")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SubnetInfo {
+    info: AddressInfo,
+}
+
+#[cfg(feature = "ipnet")]
+impl SubnetInfo {
+    /// Recover the key information from a `/64` subnet in the given network.
+    pub fn with_network(subnet: Ipv6Net, network: &Network) -> Result<Self, AddressInfoError> {
+        if subnet.prefix_len() != 64 {
+            return Err(AddressInfoError::SubnetPrefixLength(subnet.prefix_len()));
+        }
+        let info = AddressInfo::with_network(subnet.network(), network)?;
+        if !info.is_subnet() {
+            return Err(AddressInfoError::NotSubnet);
+        }
+        Ok(Self { info })
+    }
+
+    /// The network the subnet is in
+    pub fn network(&self) -> Network {
+        self.info.network()
+    }
+
+    /// The strength of the key, the number of its leading zero bits
+    pub fn strength(&self) -> u32 {
+        self.info.strength()
+    }
+
+    /// The start of the public key, valid for the first [`SubnetInfo::known_bits`] bits. The
+    /// remaining bits are zero.
+    pub fn key_bits(&self) -> &[u8; 32] {
+        self.info.key_bits()
+    }
+
+    /// The number of leading bits of the public key known from the subnet
+    pub fn known_bits(&self) -> u32 {
+        self.info.known_bits()
+    }
+}
+
+/// Recovers the key information from subnets in the public yggdrasil network.
+#[cfg(feature = "ipnet")]
+impl TryFrom<Ipv6Net> for SubnetInfo {
+    type Error = AddressInfoError;
+
+    fn try_from(subnet: Ipv6Net) -> Result<Self, AddressInfoError> {
+        Self::with_network(subnet, &Network::YGGDRASIL)
+    }
+}
+
+#[cfg(feature = "ipnet")]
+impl From<SubnetInfo> for AddressInfo {
+    fn from(info: SubnetInfo) -> Self {
+        info.info
+    }
+}
+
+/// Tells which public keys could own an address, by comparing the bits of the key recoverable
+/// from the address.
+///
+/// ```rust
+/// use core::convert::TryFrom;
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{NodeIdentity, PartialKeyMatcher};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let matcher = PartialKeyMatcher::try_from(node.address()).unwrap();
+/// assert!(matcher.matches(node.public_key_bytes()));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PartialKeyMatcher {
+    key_bits: [u8; 32],
+    full_bytes: usize,
+    mask: u8,
+}
+
+impl PartialKeyMatcher {
+    /// Whether the public key could own the address, i.e. whether it starts with the bits
+    /// recovered from it.
+    pub fn matches(&self, public_key: &[u8; 32]) -> bool {
+        public_key[..self.full_bytes] == self.key_bits[..self.full_bytes]
+            && (self.mask == 0
+                || public_key[self.full_bytes] & self.mask == self.key_bits[self.full_bytes])
+    }
+}
+
+impl From<AddressInfo> for PartialKeyMatcher {
+    fn from(info: AddressInfo) -> Self {
+        let full_bytes = (info.known_bits / 8) as usize;
+        Self {
+            key_bits: info.key_bits,
+            full_bytes,
+            mask: !(0xffu8.checked_shr(info.known_bits % 8).unwrap_or(0)),
+        }
+    }
+}
+
+#[cfg(feature = "ipnet")]
+impl From<SubnetInfo> for PartialKeyMatcher {
+    fn from(info: SubnetInfo) -> Self {
+        Self::from(info.info)
+    }
+}
+
+/// Matches keys against addresses in the public yggdrasil network.
+impl TryFrom<Ipv6Addr> for PartialKeyMatcher {
+    type Error = AddressInfoError;
+
+    fn try_from(address: Ipv6Addr) -> Result<Self, AddressInfoError> {
+        AddressInfo::try_from(address).map(Self::from)
+    }
+}
+
+/// The scheme used to derive addresses and subnets from node keys.
+///
+/// Yggdrasil has changed the way addresses are derived before, and might do so again. Every scheme
+/// this crate knows about has its own variant, and existing variants never change their
+/// behavior. Code that explicitly selects a version therefore keeps deriving the same addresses,
+/// even once newer schemes are added.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AddressVersion {
+    /// The scheme used since yggdrasil-go v0.4: the leading ones of the inverted ed25519 public
+    /// key are counted and stripped, and the remaining bits are appended after the prefix.
+    V0_4,
+}
+
+/// An address derived from a node key, together with the scheme used to derive it.
+///
+/// Addresses are ordered by their bytes, so collections of them sort stably.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeAddress {
+    address: Ipv6Addr,
+    version: AddressVersion,
+}
+
+impl NodeAddress {
+    pub(crate) fn new(address: Ipv6Addr, version: AddressVersion) -> Self {
+        Self { address, version }
+    }
+
+    /// The derived address.
+    pub fn address(&self) -> Ipv6Addr {
+        self.address
+    }
+
+    /// The scheme the address was derived with.
+    pub fn version(&self) -> AddressVersion {
+        self.version
+    }
+}
+
+impl From<NodeAddress> for Ipv6Addr {
+    fn from(address: NodeAddress) -> Ipv6Addr {
+        address.address
+    }
+}
+
+impl fmt::Display for NodeAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.address.fmt(f)
+    }
+}
+
+/// A `/64` subnet derived from a node key, together with the scheme used to derive it.
+///
+/// Subnets are ordered by their bytes, so collections of them sort stably.
+#[cfg(feature = "ipnet")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeSubnet {
+    subnet: Ipv6Net,
+    version: AddressVersion,
+}
+
+#[cfg(feature = "ipnet")]
+impl NodeSubnet {
+    pub(crate) fn new(subnet: Ipv6Net, version: AddressVersion) -> Self {
+        Self { subnet, version }
+    }
+
+    /// The derived subnet.
+    pub fn subnet(&self) -> Ipv6Net {
+        self.subnet
+    }
+
+    /// The scheme the subnet was derived with.
+    pub fn version(&self) -> AddressVersion {
+        self.version
+    }
+
+    /// Derive a stable address for a service within this subnet.
+    ///
+    /// The interface identifier (the lower 64 bits) is taken from the SHA-512 hash of the
+    /// upper 64 bits of the subnet followed by the label, so the same label always maps to the
+    /// same address inside a given subnet, while different subnets get unrelated addresses for
+    /// the same label.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::{AddressVersion, NodeIdentity};
+    ///
+    /// let node = NodeIdentity::new(&mut thread_rng());
+    /// let subnet = node.versioned_subnet(AddressVersion::V0_4);
+    /// let www = subnet.service_address("www");
+    /// assert!(subnet.subnet().contains(&www));
+    /// assert_eq!(www, subnet.service_address("www"));
+    /// ```
+    pub fn service_address(&self, label: &str) -> Ipv6Addr {
+        let mut bytes = self.subnet.network().octets();
+        let hash = Sha512::new()
+            .chain_update(&bytes[0..8])
+            .chain_update(label.as_bytes())
+            .finalize();
+        bytes[8..16].copy_from_slice(&hash[0..8]);
+        Ipv6Addr::from(bytes)
+    }
+}
+
+#[cfg(feature = "ipnet")]
+impl From<NodeSubnet> for Ipv6Net {
+    fn from(subnet: NodeSubnet) -> Ipv6Net {
+        subnet.subnet
+    }
+}
+
+#[cfg(feature = "ipnet")]
+impl fmt::Display for NodeSubnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.subnet.fmt(f)
+    }
+}
@@ -0,0 +1,442 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::{Signature, Signer, VerifyingKey};
+#[cfg(feature = "pkcs8")]
+use rand_core::{CryptoRng, RngCore};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+use crate::{
+    audit::{audit, KeyOperation},
+    helper::{field, public_hex_to_bytes, replace_secret_file},
+    verify_signature, ArchiveError, FromHexError, NodeIdentity, VerificationMode,
+};
+
+/// Domain separation prefix for the signatures of [`ArchiveEntry`]s
+const ENTRY_SIGNATURE_CONTEXT: &[u8] = b"yggdrasil-keys archive entry v1";
+
+const BEGIN: &str = "-----BEGIN YGGDRASIL KEY ARCHIVE-----";
+const END: &str = "-----END YGGDRASIL KEY ARCHIVE-----";
+
+const MISSING_FIELD: ArchiveError = ArchiveError::Malformed("missing or misplaced field");
+
+/// Several labelled node identities with metadata, stored in a single text file.
+///
+/// Every entry is signed by its node key, like a [`crate::KeyFile`], so the labels and metadata
+/// can't be changed without access to the keys. With the `pkcs8` feature, the secret keys of
+/// entries can be encrypted with a password. Encrypted entries can be listed without the password,
+/// only extracting the identity requires it. An archive looks like this:
+///
+/// ```text
+/// -----BEGIN YGGDRASIL KEY ARCHIVE-----
+/// Version: 1
+///
+/// Label: <single line of text>
+/// Created: <unix timestamp>
+/// Comment: <single line of text>
+/// Public-Key: <hex>
+/// Secret-Key: <hex>
+/// Signature: <hex>
+///
+/// Label: <single line of text>
+/// ...
+/// Encrypted-Key: <hex encoded encrypted PKCS#8 DER>
+/// Signature: <hex>
+/// -----END YGGDRASIL KEY ARCHIVE-----
+/// ```
+///
+/// The signatures cover the context `yggdrasil-keys archive entry v1`, the version as four big
+/// endian bytes, the creation time as eight big endian bytes, the public key, the length of the
+/// label as four big endian bytes, the label and the comment.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{KeyArchive, NodeIdentity};
+///
+/// let mut archive = KeyArchive::new();
+/// archive.add("alpha", NodeIdentity::new(&mut thread_rng()), "first node").unwrap();
+/// archive.add("beta", NodeIdentity::new(&mut thread_rng()), "").unwrap();
+///
+/// let decoded = KeyArchive::decode(&archive.encode()).unwrap();
+/// let labels: Vec<_> = decoded.list().map(|entry| entry.label()).collect();
+/// assert_eq!(labels, ["alpha", "beta"]);
+/// let alpha = decoded.extract("alpha").unwrap();
+/// assert_eq!(alpha.address(), archive.extract("alpha").unwrap().address());
+/// ```
+#[derive(Default)]
+pub struct KeyArchive {
+    /// In insertion order
+    entries: Vec<ArchiveEntry>,
+}
+
+/// A labelled node identity in a [`KeyArchive`].
+pub struct ArchiveEntry {
+    label: String,
+    comment: String,
+    created: u64,
+    public_key: VerifyingKey,
+    secret: Secret,
+    signature: Signature,
+}
+
+enum Secret {
+    Plain(NodeIdentity),
+    /// An encrypted PKCS#8 document
+    Encrypted(Vec<u8>),
+}
+
+impl KeyArchive {
+    /// The version of the format written by this crate
+    pub const VERSION: u32 = 1;
+
+    /// Create an empty archive.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an identity under a new label, with a comment and the current time.
+    ///
+    /// Labels have to be unique within the archive, and neither labels nor comments may contain
+    /// line breaks.
+    pub fn add(
+        &mut self,
+        label: impl Into<String>,
+        identity: NodeIdentity,
+        comment: impl Into<String>,
+    ) -> Result<(), ArchiveError> {
+        let public_key = identity.signing_keys.verifying_key();
+        let (label, comment, created) = self.check_new_entry(label.into(), comment.into())?;
+        audit(&identity, KeyOperation::Sign, "KeyArchive::add");
+        let signature = identity.signing_keys.sign(&entry_signature_message(
+            Self::VERSION,
+            created,
+            &public_key,
+            &label,
+            &comment,
+        ));
+        self.entries.push(ArchiveEntry {
+            label,
+            comment,
+            created,
+            public_key,
+            secret: Secret::Plain(identity),
+            signature,
+        });
+        Ok(())
+    }
+
+    /// Add an identity under a new label like [`KeyArchive::add`], encrypting its secret key
+    /// with the password.
+    ///
+    /// The key is encrypted like [`NodeIdentity::to_encrypted_pkcs8_pem`] does. Requires the
+    /// `pkcs8` feature.
+    #[cfg(feature = "pkcs8")]
+    pub fn add_encrypted<R: CryptoRng + RngCore>(
+        &mut self,
+        csprng: &mut R,
+        label: impl Into<String>,
+        identity: &NodeIdentity,
+        comment: impl Into<String>,
+        password: impl AsRef<[u8]>,
+    ) -> Result<(), ArchiveError> {
+        let public_key = identity.signing_keys.verifying_key();
+        let (label, comment, created) = self.check_new_entry(label.into(), comment.into())?;
+        audit(identity, KeyOperation::Encrypt, "KeyArchive::add_encrypted");
+        let encrypted = identity.to_encrypted_pkcs8_der(csprng, password)?;
+        let signature = identity.signing_keys.sign(&entry_signature_message(
+            Self::VERSION,
+            created,
+            &public_key,
+            &label,
+            &comment,
+        ));
+        self.entries.push(ArchiveEntry {
+            label,
+            comment,
+            created,
+            public_key,
+            secret: Secret::Encrypted(encrypted.as_bytes().to_vec()),
+            signature,
+        });
+        Ok(())
+    }
+
+    /// Remove the entry with the given label from the archive.
+    pub fn remove(&mut self, label: &str) -> Option<ArchiveEntry> {
+        self.position(label).map(|index| self.entries.remove(index))
+    }
+
+    /// All entries, in the order they were added.
+    pub fn list(&self) -> impl Iterator<Item = &ArchiveEntry> {
+        self.entries.iter()
+    }
+
+    /// Look up the entry with the given label.
+    pub fn get(&self, label: &str) -> Option<&ArchiveEntry> {
+        self.position(label).map(|index| &self.entries[index])
+    }
+
+    /// Extract the identity with the given label.
+    ///
+    /// Fails for encrypted entries, see [`KeyArchive::extract_encrypted`] for those.
+    pub fn extract(&self, label: &str) -> Result<NodeIdentity, ArchiveError> {
+        match &self.entry(label)?.secret {
+            Secret::Plain(identity) => Ok(identity.clone()),
+            Secret::Encrypted(_) => Err(ArchiveError::Encrypted),
+        }
+    }
+
+    /// Extract the identity with the given label, decrypting it with the password if necessary.
+    ///
+    /// Requires the `pkcs8` feature.
+    #[cfg(feature = "pkcs8")]
+    pub fn extract_encrypted(
+        &self,
+        label: &str,
+        password: impl AsRef<[u8]>,
+    ) -> Result<NodeIdentity, ArchiveError> {
+        use ed25519_dalek::{pkcs8::DecodePrivateKey, SigningKey};
+
+        let entry = self.entry(label)?;
+        let Secret::Encrypted(encrypted) = &entry.secret else {
+            return self.extract(label);
+        };
+        let signing_keys = SigningKey::from_pkcs8_encrypted_der(encrypted, password)?;
+        if signing_keys.verifying_key() != entry.public_key {
+            return Err(ArchiveError::InvalidKey(FromHexError::WrongPublicKey));
+        }
+        Ok(NodeIdentity { signing_keys })
+    }
+
+    /// The number of entries in the archive
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the archive contains no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Encode the archive, including the secret keys.
+    pub fn encode(&self) -> Zeroizing<String> {
+        let mut encoded = Zeroizing::new(format!("{}\nVersion: {}\n", BEGIN, Self::VERSION));
+        for entry in &self.entries {
+            let secret = match &entry.secret {
+                Secret::Plain(identity) => {
+                    audit(identity, KeyOperation::Export, "KeyArchive::encode");
+                    Zeroizing::new(format!("Secret-Key: {}", identity.hex_split().0))
+                }
+                Secret::Encrypted(encrypted) => {
+                    Zeroizing::new(format!("Encrypted-Key: {}", hex::encode(encrypted)))
+                }
+            };
+            encoded.push_str(&format!(
+                "\nLabel: {}\nCreated: {}\nComment: {}\nPublic-Key: {}\n",
+                entry.label,
+                entry.created,
+                entry.comment,
+                hex::encode(entry.public_key.as_bytes()),
+            ));
+            encoded.push_str(&secret);
+            encoded.push_str(&format!(
+                "\nSignature: {}\n",
+                hex::encode(entry.signature.to_bytes())
+            ));
+        }
+        encoded.push_str(END);
+        encoded.push('\n');
+        encoded
+    }
+
+    /// Decode an archive, checking that the keys belong together and all signatures are valid.
+    ///
+    /// Encrypted entries can only be checked against their public keys once they are extracted.
+    pub fn decode(encoded: &str) -> Result<Self, ArchiveError> {
+        let mut lines = encoded.lines();
+        if lines.next() != Some(BEGIN) {
+            return Err(ArchiveError::Malformed("missing begin marker"));
+        }
+        let version = field(&mut lines, "Version")
+            .ok_or(MISSING_FIELD)?
+            .parse()
+            .map_err(|_| ArchiveError::Malformed("version is not a number"))?;
+        if version != Self::VERSION {
+            return Err(ArchiveError::UnsupportedVersion(version));
+        }
+
+        let mut archive = Self::new();
+        loop {
+            match lines.next() {
+                Some("") => {}
+                Some(END) => break,
+                _ => return Err(ArchiveError::Malformed("missing end marker")),
+            }
+            let label = field(&mut lines, "Label").ok_or(MISSING_FIELD)?.to_owned();
+            let created = field(&mut lines, "Created")
+                .ok_or(MISSING_FIELD)?
+                .parse()
+                .map_err(|_| ArchiveError::Malformed("creation time is not a number"))?;
+            if UNIX_EPOCH
+                .checked_add(Duration::from_secs(created))
+                .is_none()
+            {
+                return Err(ArchiveError::Malformed("creation time is out of range"));
+            }
+            let comment = field(&mut lines, "Comment")
+                .ok_or(MISSING_FIELD)?
+                .to_owned();
+            let public_hex = field(&mut lines, "Public-Key").ok_or(MISSING_FIELD)?;
+            let public_key = VerifyingKey::from_bytes(&public_hex_to_bytes(public_hex)?)
+                .map_err(FromHexError::from)?;
+            let secret = match lines.next().and_then(|line| line.split_once(": ")) {
+                Some(("Secret-Key", secret_hex)) => {
+                    Secret::Plain(NodeIdentity::from_hex(secret_hex, Some(public_hex))?)
+                }
+                Some(("Encrypted-Key", encrypted)) => Secret::Encrypted(
+                    hex::decode(encrypted)
+                        .map_err(|_| ArchiveError::Malformed("encrypted key is not hex"))?,
+                ),
+                _ => return Err(ArchiveError::Malformed("missing or misplaced field")),
+            };
+            let mut signature = [0u8; 64];
+            hex::decode_to_slice(
+                field(&mut lines, "Signature").ok_or(MISSING_FIELD)?,
+                &mut signature,
+            )
+            .map_err(|_| ArchiveError::Malformed("signature is not 64 hex encoded bytes"))?;
+            let signature = Signature::from_bytes(&signature);
+
+            if archive.position(&label).is_some() {
+                return Err(ArchiveError::DuplicateLabel(label));
+            }
+            verify_signature(
+                &public_key,
+                &entry_signature_message(version, created, &public_key, &label, &comment),
+                &signature,
+                VerificationMode::Strict,
+            )?;
+            archive.entries.push(ArchiveEntry {
+                label,
+                comment,
+                created,
+                public_key,
+                secret,
+                signature,
+            });
+        }
+        if lines.any(|line| !line.is_empty()) {
+            return Err(ArchiveError::Malformed("trailing data after end marker"));
+        }
+        Ok(archive)
+    }
+
+    /// Write the archive to a file, only readable by its owner on Unix.
+    ///
+    /// The archive is written next to the destination first and then moved into place, replacing
+    /// the previous version of the archive without destroying it on interrupted saves.
+    pub fn save(&self, path: &Path) -> Result<(), ArchiveError> {
+        replace_secret_file(path, self.encode().as_bytes())?;
+        Ok(())
+    }
+
+    /// Read and decode an archive.
+    pub fn load(path: &Path) -> Result<Self, ArchiveError> {
+        let encoded = Zeroizing::new(fs::read_to_string(path)?);
+        Self::decode(&encoded)
+    }
+
+    fn check_new_entry(
+        &self,
+        label: String,
+        comment: String,
+    ) -> Result<(String, String, u64), ArchiveError> {
+        if label.is_empty() || label.contains(['\n', '\r']) {
+            return Err(ArchiveError::InvalidLabel);
+        }
+        if comment.contains(['\n', '\r']) {
+            return Err(ArchiveError::InvalidComment);
+        }
+        if self.position(&label).is_some() {
+            return Err(ArchiveError::DuplicateLabel(label));
+        }
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        Ok((label, comment, created))
+    }
+
+    fn entry(&self, label: &str) -> Result<&ArchiveEntry, ArchiveError> {
+        self.get(label)
+            .ok_or_else(|| ArchiveError::UnknownLabel(label.to_owned()))
+    }
+
+    fn position(&self, label: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.label == label)
+    }
+}
+
+impl ArchiveEntry {
+    /// The label identifying the entry within its archive
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The comment describing the key
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// When the entry was added
+    pub fn created(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.created)
+    }
+
+    /// The public key of the identity
+    pub fn public_key(&self) -> VerifyingKey {
+        self.public_key
+    }
+
+    /// Whether the secret key is encrypted with a password
+    pub fn is_encrypted(&self) -> bool {
+        matches!(self.secret, Secret::Encrypted(_))
+    }
+}
+
+fn entry_signature_message(
+    version: u32,
+    created: u64,
+    public: &VerifyingKey,
+    label: &str,
+    comment: &str,
+) -> Vec<u8> {
+    [
+        ENTRY_SIGNATURE_CONTEXT,
+        &version.to_be_bytes(),
+        &created.to_be_bytes(),
+        public.as_bytes(),
+        &(label.len() as u32).to_be_bytes(),
+        label.as_bytes(),
+        comment.as_bytes(),
+    ]
+    .concat()
+}
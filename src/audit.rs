@@ -0,0 +1,115 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::VerifyingKey;
+#[cfg(feature = "std")]
+use std::sync::{Arc, RwLock};
+
+use crate::NodeIdentity;
+
+/// What a secret key was used for, see [`KeyUsage`].
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyOperation {
+    /// Signing a message, token or certificate
+    Sign,
+    /// Encoding the secret key in plain text or binary form
+    Export,
+    /// Encoding the secret key encrypted with a password
+    Encrypt,
+    /// Deriving a shared secret with a peer key
+    KeyExchange,
+}
+
+/// Metadata about a use of a secret key, passed to the hook installed with [`set_audit_hook`].
+///
+/// This never contains key material, only the public key identifying the node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyUsage {
+    /// What the key was used for
+    pub operation: KeyOperation,
+    /// The public key of the identity that was used
+    pub public_key: VerifyingKey,
+    /// The function of this crate that used the key, like `"KeyFile::encode"`
+    pub context: &'static str,
+}
+
+#[cfg(feature = "std")]
+type Hook = Arc<dyn Fn(&KeyUsage) + Send + Sync>;
+
+#[cfg(feature = "std")]
+static HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+
+/// Install a hook that is called whenever this crate uses a secret key, replacing any previous
+/// hook.
+///
+/// The hook is called for every signature, export and key exchange done by functions of this
+/// crate, before the key is used, so deployments can log and alert on unexpected key usage.
+/// Using the key pair returned by `NodeIdentity::signing_keys` (with the `hazmat` feature)
+/// bypasses the hook. Requires the `std` feature.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use yggdrasil_keys::{set_audit_hook, KeyOperation, NodeIdentity};
+///
+/// static EXPORTS: AtomicUsize = AtomicUsize::new(0);
+/// set_audit_hook(|usage| {
+///     if usage.operation == KeyOperation::Export {
+///         EXPORTS.fetch_add(1, Ordering::Relaxed);
+///     }
+/// });
+/// NodeIdentity::new(&mut thread_rng()).to_hex_joined();
+/// assert_eq!(EXPORTS.load(Ordering::Relaxed), 1);
+/// ```
+#[cfg(feature = "std")]
+pub fn set_audit_hook(hook: impl Fn(&KeyUsage) + Send + Sync + 'static) {
+    *HOOK
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Arc::new(hook));
+}
+
+/// Remove the hook installed with [`set_audit_hook`]. Requires the `std` feature.
+#[cfg(feature = "std")]
+pub fn clear_audit_hook() {
+    *HOOK
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+}
+
+/// Report a use of the secret key of an identity to the audit hook.
+pub(crate) fn audit(identity: &NodeIdentity, operation: KeyOperation, context: &'static str) {
+    #[cfg(feature = "std")]
+    {
+        // Cloned out of the lock, so hooks may use keys themselves
+        let hook = HOOK
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        if let Some(hook) = hook {
+            hook(&KeyUsage {
+                operation,
+                public_key: identity.signing_keys.verifying_key(),
+                context,
+            });
+        }
+    }
+    #[cfg(not(feature = "std"))]
+    let _ = (identity, operation, context);
+}
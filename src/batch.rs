@@ -0,0 +1,56 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::{Signature, SignatureError, VerifyingKey};
+
+use crate::PublicNodeIdentity;
+
+/// Verify many signatures by different nodes at once.
+///
+/// This uses the batch verification of ed25519-dalek, which is considerably faster than
+/// verifying the signatures one by one. The three slices belong together by index, and have to
+/// have the same length.
+///
+/// The batch is only valid as a whole: if any signature is invalid, an error is returned without
+/// telling which one. Callers that need to know can fall back to
+/// [`PublicNodeIdentity::verify`] for each signature. Batch verification follows
+/// [`crate::VerificationMode::Standard`], except for edge cases involving small order points, so
+/// it can't be used where [`crate::VerificationMode::Strict`] is required.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{verify_batch, NodeIdentity, PublicNodeIdentity};
+///
+/// let nodes: Vec<_> = (0..16).map(|_| NodeIdentity::new(&mut thread_rng())).collect();
+/// let messages: Vec<&[u8]> = vec![b"announcement"; nodes.len()];
+/// let signatures: Vec<_> = nodes.iter().map(|node| node.sign(b"announcement")).collect();
+/// let identities: Vec<PublicNodeIdentity> = nodes.iter().map(NodeIdentity::to_public).collect();
+/// assert!(verify_batch(&messages, &signatures, &identities).is_ok());
+/// ```
+pub fn verify_batch(
+    messages: &[&[u8]],
+    signatures: &[Signature],
+    public_identities: &[PublicNodeIdentity],
+) -> Result<(), SignatureError> {
+    let public_keys: Vec<VerifyingKey> = public_identities
+        .iter()
+        .map(PublicNodeIdentity::public_key)
+        .collect();
+    ed25519_dalek::verify_batch(messages, signatures, &public_keys)
+}
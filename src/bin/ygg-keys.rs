@@ -0,0 +1,103 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+//! Command line tool for provisioning yggdrasil keys.
+//!
+//! ```text
+//! ygg-keys fleet --count N --out-dir DIR [--seed-file FILE]
+//! ```
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use rand_core::OsRng;
+use yggdrasil_keys::{derive_fleet, read_fleet_seed, write_fleet, NodeIdentity, INVENTORY_FILE};
+
+const USAGE: &str = "usage: ygg-keys fleet --count N --out-dir DIR [--seed-file FILE]";
+
+/// Options of the `fleet` subcommand
+struct FleetOptions {
+    count: u64,
+    out_dir: PathBuf,
+    seed_file: Option<PathBuf>,
+}
+
+impl FleetOptions {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let (mut count, mut out_dir, mut seed_file) = (None, None, None);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+            match arg.as_str() {
+                "--count" => {
+                    let value = value()?;
+                    count = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid count: {}", value))?,
+                    );
+                }
+                "--out-dir" => out_dir = Some(PathBuf::from(value()?)),
+                "--seed-file" => seed_file = Some(PathBuf::from(value()?)),
+                _ => return Err(format!("unknown argument: {}", arg)),
+            }
+        }
+        Ok(Self {
+            count: count.ok_or("missing --count")?,
+            out_dir: out_dir.ok_or("missing --out-dir")?,
+            seed_file,
+        })
+    }
+
+    /// Generate or derive the identities and write them into the output directory.
+    fn run(&self) -> Result<(), String> {
+        let nodes = match &self.seed_file {
+            Some(seed_file) => {
+                let seed = read_fleet_seed(seed_file)
+                    .map_err(|error| format!("{}: {}", seed_file.display(), error))?;
+                derive_fleet(&seed, self.count)
+            }
+            None => (0..self.count)
+                .map(|_| NodeIdentity::new(&mut OsRng))
+                .collect(),
+        };
+        write_fleet((0..).zip(&nodes), &self.out_dir)
+            .map_err(|error| format!("{}: {}", self.out_dir.display(), error))?;
+        println!(
+            "wrote {} keys and {}",
+            nodes.len(),
+            self.out_dir.join(INVENTORY_FILE).display()
+        );
+        Ok(())
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let result = match args.next().as_deref() {
+        Some("fleet") => FleetOptions::parse(args).and_then(|options| options.run()),
+        _ => Err(USAGE.to_owned()),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("ygg-keys: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,87 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+//! Bit utilities for processing raw public keys.
+//!
+//! Yggdrasil addresses are built from the leading ones of the inverted public key, which are
+//! counted and stripped, followed by the bits after them. These functions do the same for
+//! arbitrary byte slices, without allocating.
+//!
+//! ```rust
+//! use yggdrasil_keys::bits;
+//!
+//! let mut key = [0x00, 0x1f, 0xff];
+//! bits::invert(&mut key);
+//! assert_eq!(bits::leading_ones(&key), 11);
+//! let mut remainder = [0u8; 3];
+//! assert_eq!(bits::strip_ones(&key, &mut remainder), Ok(11));
+//! assert_eq!(remainder, [0x00, 0x00, 0x00]);
+//! ```
+
+use crate::BitsError;
+
+/// Count the leading ones of a byte slice, starting at the most significant bit of the first
+/// byte.
+///
+/// Empty slices have no leading ones, slices of only ones have eight per byte.
+pub fn leading_ones(bytes: &[u8]) -> usize {
+    let mut leading_ones = 0;
+    for byte in bytes {
+        let local_leading_ones = byte.leading_ones() as usize;
+        leading_ones += local_leading_ones;
+        // Stop at the first zero
+        if local_leading_ones != 8 {
+            break;
+        }
+    }
+    leading_ones
+}
+
+/// Count the leading ones of a byte slice, strip them plus the following zero off, and write the
+/// remaining bits into `remainder`, padded with zeroes. Returns the number of leading ones.
+///
+/// The remainder has to be at least as long as the input. If the input consists only of ones,
+/// there is no zero to strip and the remainder is all zeroes.
+pub fn strip_ones(bytes: &[u8], remainder: &mut [u8]) -> Result<usize, BitsError> {
+    if remainder.len() < bytes.len() {
+        return Err(BitsError::BufferTooSmall(bytes.len()));
+    }
+    let ones = leading_ones(bytes);
+    let strip = ones + 1;
+    let skip = (strip / 8).min(bytes.len());
+    let shift = strip % 8;
+    let rest = &bytes[skip..];
+    remainder.fill(0);
+    for (index, byte) in rest.iter().enumerate() {
+        remainder[index] = if shift == 0 {
+            *byte
+        } else {
+            let next = rest.get(index + 1).copied().unwrap_or(0);
+            (byte << shift) | (next >> (8 - shift))
+        };
+    }
+    Ok(ones)
+}
+
+/// Invert all bits of a byte slice in place.
+pub fn invert(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        *byte = !*byte;
+    }
+}
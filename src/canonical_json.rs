@@ -0,0 +1,143 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use base64ct::{Base64Unpadded, Encoding};
+use ed25519_dalek::{Signature, Signer, VerifyingKey};
+use serde_json::{Map, Value};
+
+use crate::{
+    audit::{audit, KeyOperation},
+    helper::sorted_json,
+    verify_signature, JsonSignatureError, NodeIdentity, VerificationMode,
+};
+
+/// The largest integer allowed in canonical JSON, `2^53 - 1`.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+/// Encode a JSON value as Matrix-style canonical JSON.
+///
+/// Object keys are sorted by their codepoints and no insignificant whitespace is emitted.
+/// Only integers in the range `[-(2^53)+1, (2^53)-1]` are allowed as numbers.
+pub fn canonical_json(value: &Value) -> Result<String, JsonSignatureError> {
+    check_numbers(value)?;
+    Ok(sorted_json(value))
+}
+
+/// Sign a JSON object with the node key, as done in Matrix-style protocols.
+///
+/// The `signatures` and `unsigned` members are left out of the signed canonical JSON, and the
+/// unpadded base64 signature is added as `signatures.<entity>."ed25519:<key_name>"`. Existing
+/// signatures are kept, and `unsigned` is left in place.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use serde_json::json;
+/// use yggdrasil_keys::{sign_json, verify_json, NodeIdentity};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let mut object = json!({ "hello": "world" });
+/// sign_json(&node, "node.example", "1", &mut object).unwrap();
+///
+/// let public_key = node.public_key();
+/// verify_json(&object, "node.example", "1", &public_key).unwrap();
+/// ```
+pub fn sign_json(
+    identity: &NodeIdentity,
+    entity: &str,
+    key_name: &str,
+    object: &mut Value,
+) -> Result<(), JsonSignatureError> {
+    let message = signed_json(object)?;
+    audit(identity, KeyOperation::Sign, "sign_json");
+    let signature = identity.signing_keys.sign(message.as_bytes());
+    let object = object
+        .as_object_mut()
+        .ok_or(JsonSignatureError::NotAnObject)?;
+    let signatures = object
+        .entry("signatures")
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or(JsonSignatureError::MalformedSignature)?;
+    let entity = signatures
+        .entry(entity)
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or(JsonSignatureError::MalformedSignature)?;
+    entity.insert(
+        format!("ed25519:{}", key_name),
+        Value::String(Base64Unpadded::encode_string(&signature.to_bytes())),
+    );
+    Ok(())
+}
+
+/// Verify the signature of the given entity and key on a JSON object.
+pub fn verify_json(
+    object: &Value,
+    entity: &str,
+    key_name: &str,
+    public_key: &VerifyingKey,
+) -> Result<(), JsonSignatureError> {
+    let key_id = format!("ed25519:{}", key_name);
+    let signature = object
+        .as_object()
+        .ok_or(JsonSignatureError::NotAnObject)?
+        .get("signatures")
+        .and_then(|signatures| signatures.get(entity))
+        .and_then(|entity| entity.get(&key_id))
+        .ok_or(JsonSignatureError::MissingSignature(key_id))?
+        .as_str()
+        .ok_or(JsonSignatureError::MalformedSignature)?;
+    let mut bytes = [0u8; 64];
+    match Base64Unpadded::decode(signature, &mut bytes) {
+        Ok(decoded) if decoded.len() == 64 => {}
+        _ => return Err(JsonSignatureError::MalformedSignature),
+    }
+
+    let message = signed_json(object)?;
+    verify_signature(
+        public_key,
+        message.as_bytes(),
+        &Signature::from_bytes(&bytes),
+        VerificationMode::Strict,
+    )
+    .map_err(|_| JsonSignatureError::InvalidSignature)
+}
+
+/// The canonical JSON of an object without its `signatures` and `unsigned` members.
+fn signed_json(object: &Value) -> Result<String, JsonSignatureError> {
+    let mut object = object
+        .as_object()
+        .ok_or(JsonSignatureError::NotAnObject)?
+        .clone();
+    object.remove("signatures");
+    object.remove("unsigned");
+    canonical_json(&Value::Object(object))
+}
+
+fn check_numbers(value: &Value) -> Result<(), JsonSignatureError> {
+    match value {
+        Value::Number(number) => match number.as_i64() {
+            Some(number) if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&number) => Ok(()),
+            _ => Err(JsonSignatureError::NonCanonicalNumber),
+        },
+        Value::Array(values) => values.iter().try_for_each(check_numbers),
+        Value::Object(members) => members.values().try_for_each(check_numbers),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,154 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::{Signature, SignatureError, Signer, VerifyingKey};
+#[cfg(feature = "rand")]
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::net::Ipv6Addr;
+
+use crate::{
+    audit::{audit, KeyOperation},
+    helper::address_bytes,
+    verify_signature, NodeIdentity, VerificationMode,
+};
+
+/// Domain separation prefix for the signatures in [`Response`]s
+const CHALLENGE_CONTEXT: &[u8] = b"yggdrasil-keys challenge v1";
+
+/// A challenge for proving control over a node key, independent of the transport.
+///
+/// The verifier creates a challenge with a fresh nonce and sends the nonce to the peer. The peer
+/// recreates the challenge from the nonce, and sends back its response. Both sides have to use
+/// the same context, which binds the response to its purpose, e.g. the protocol and session, so
+/// it can't be replayed elsewhere.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{Challenge, NodeIdentity};
+///
+/// // On the verifier:
+/// let challenge = Challenge::new(&mut thread_rng(), b"example login");
+///
+/// // On the peer:
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let response = Challenge::from_nonce(*challenge.nonce(), b"example login").respond(&node);
+///
+/// // Back on the verifier:
+/// assert_eq!(challenge.verify(&response).unwrap(), node.address());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Challenge {
+    nonce: [u8; 32],
+    context: Vec<u8>,
+}
+
+impl Challenge {
+    /// Create a challenge with a random nonce for the given context.
+    ///
+    /// Requires the `rand` feature.
+    #[cfg(feature = "rand")]
+    pub fn new<R: CryptoRng + RngCore>(csprng: &mut R, context: &[u8]) -> Self {
+        let mut nonce = [0u8; 32];
+        csprng.fill_bytes(&mut nonce);
+        Self::from_nonce(nonce, context)
+    }
+
+    /// Recreate a challenge from a nonce received from the verifier.
+    pub fn from_nonce(nonce: [u8; 32], context: &[u8]) -> Self {
+        Self {
+            nonce,
+            context: context.to_vec(),
+        }
+    }
+
+    /// The nonce to send to the peer
+    pub fn nonce(&self) -> &[u8; 32] {
+        &self.nonce
+    }
+
+    /// Respond to the challenge, proving control over the given identity.
+    pub fn respond(&self, identity: &NodeIdentity) -> Response {
+        audit(identity, KeyOperation::Sign, "Challenge::respond");
+        let signature = identity.signing_keys.sign(&self.message());
+        Response {
+            public_key: identity.signing_keys.verifying_key().to_bytes(),
+            signature: signature.to_bytes(),
+        }
+    }
+
+    /// Check the response to this challenge, and return the address of the responding node.
+    ///
+    /// Compare the address to the one the peer claimed to make sure it controls that address.
+    pub fn verify(&self, response: &Response) -> Result<Ipv6Addr, SignatureError> {
+        verify_signature(
+            &VerifyingKey::from_bytes(&response.public_key)?,
+            &self.message(),
+            &Signature::from_bytes(&response.signature),
+            VerificationMode::Strict,
+        )?;
+        Ok(response.address())
+    }
+
+    fn message(&self) -> Vec<u8> {
+        [CHALLENGE_CONTEXT, &self.nonce, &self.context].concat()
+    }
+}
+
+/// The response to a [`Challenge`], containing the public key of the node and its signature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Response {
+    #[cfg_attr(feature = "serde", serde(with = "crate::helper::serde_hex"))]
+    public_key: [u8; 32],
+    #[cfg_attr(feature = "serde", serde(with = "crate::helper::serde_hex"))]
+    signature: [u8; 64],
+}
+
+impl Response {
+    /// Reassemble a response received from a peer.
+    pub fn new(public_key: [u8; 32], signature: [u8; 64]) -> Self {
+        Self {
+            public_key,
+            signature,
+        }
+    }
+
+    /// The ed25519 public key of the responding node
+    pub fn public_key(&self) -> &[u8; 32] {
+        &self.public_key
+    }
+
+    /// The signature over the challenge
+    pub fn signature(&self) -> &[u8; 64] {
+        &self.signature
+    }
+
+    /// The address of the responding node, with the default IP prefix.
+    ///
+    /// This is unverified until the response was checked with [`Challenge::verify`].
+    pub fn address(&self) -> Ipv6Addr {
+        Ipv6Addr::from(address_bytes(
+            &self.public_key,
+            &NodeIdentity::IP_PREFIX,
+            false,
+        ))
+    }
+}
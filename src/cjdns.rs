@@ -0,0 +1,108 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use core::net::Ipv6Addr;
+
+use sha2::{Digest, Sha512};
+
+use crate::{CjdnsError, NodeIdentity};
+
+/// The alphabet of the base32 variant used for cjdns keys
+const CJDNS_BASE32: &[u8; 32] = b"0123456789bcdfghjklmnpqrstuvwxyz";
+
+/// The overlay network an address belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Overlay {
+    /// Yggdrasil addresses in `200::/8` and subnets in `300::/8`
+    Yggdrasil,
+    /// cjdns addresses in `fc00::/8`
+    Cjdns,
+}
+
+impl Overlay {
+    /// Detect which overlay an address belongs to, based on its prefix.
+    pub fn detect(address: &Ipv6Addr) -> Option<Self> {
+        match address.octets()[0] {
+            0x02 | 0x03 => Some(Self::Yggdrasil),
+            0xfc => Some(Self::Cjdns),
+            _ => None,
+        }
+    }
+}
+
+/// Derive the cjdns address of a curve25519 public key.
+///
+/// cjdns addresses are the first 16 bytes of the double SHA-512 hash of the key, and only
+/// keys for which this lands in `fc00::/8` are valid cjdns keys.
+pub fn cjdns_address(public_key: &[u8; 32]) -> Option<Ipv6Addr> {
+    let hash = Sha512::digest(Sha512::digest(public_key));
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[0..16]);
+    Some(Ipv6Addr::from(bytes)).filter(|_| bytes[0] == 0xfc)
+}
+
+/// Parse a cjdns public key in its base32 form, with or without the `.k` suffix.
+///
+/// ```rust
+/// use yggdrasil_keys::{cjdns_address, parse_cjdns_key};
+///
+/// let key = parse_cjdns_key("r6jzx210usqbgnm3pdtm1z6btd14pvdtkn5j8qnpgqzknpggkuw0.k").unwrap();
+/// assert_eq!(
+///     cjdns_address(&key).unwrap(),
+///     "fc68:cb2c:60db:cb96:19ac:34a8:fd34:3fc".parse::<std::net::Ipv6Addr>().unwrap()
+/// );
+/// ```
+pub fn parse_cjdns_key(key: &str) -> Result<[u8; 32], CjdnsError> {
+    let key = key.strip_suffix(".k").unwrap_or(key);
+    if key.len() != 52 {
+        return Err(CjdnsError::MalformedKey);
+    }
+
+    // cjdns fills the bits of each character starting from the least significant one
+    let mut bytes = [0u8; 32];
+    let (mut bits, mut bit_count, mut index) = (0u16, 0, 0);
+    for character in key.bytes() {
+        let value = CJDNS_BASE32
+            .iter()
+            .position(|symbol| *symbol == character)
+            .ok_or(CjdnsError::MalformedKey)?;
+        bits |= (value as u16) << bit_count;
+        bit_count += 5;
+        if bit_count >= 8 {
+            bytes[index] = bits as u8;
+            bits >>= 8;
+            bit_count -= 8;
+            index += 1;
+        }
+    }
+    if bits != 0 {
+        return Err(CjdnsError::MalformedKey);
+    }
+    Ok(bytes)
+}
+
+impl NodeIdentity {
+    /// The cjdns address of the curve25519 form of the node key.
+    ///
+    /// Only about one in 256 node keys maps into `fc00::/8`, so this is `None` for most keys.
+    pub fn cjdns_address(&self) -> Option<Ipv6Addr> {
+        let montgomery = self.signing_keys.verifying_key().to_montgomery();
+        cjdns_address(montgomery.as_bytes())
+    }
+}
@@ -0,0 +1,133 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ipnet::Ipv6Net;
+use serde::{Deserialize, Serialize};
+use std::net::Ipv6Addr;
+use zeroize::Zeroizing;
+
+use crate::{
+    audit::{audit, KeyOperation},
+    NodeIdentity, PeerDb, TomlError,
+};
+
+/// The `[identity]` table written by [`NodeIdentity::to_toml`]
+#[derive(Serialize, Deserialize)]
+struct IdentityTable {
+    /// The keypair, in the format yggdrasil uses for `PrivateKey`
+    secret_key: Zeroizing<String>,
+    public_key: String,
+    /// Only informational, checked when present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    address: Option<Ipv6Addr>,
+    /// Only informational, checked when present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    subnet: Option<Ipv6Net>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IdentityDocument {
+    identity: IdentityTable,
+}
+
+impl NodeIdentity {
+    /// Serialize the identity as an `[identity]` table, for embedding into TOML configs.
+    ///
+    /// The table contains the keypair, the public key, and the derived address and subnet:
+    ///
+    /// ```toml
+    /// [identity]
+    /// secret_key = "<128 hex characters>"
+    /// public_key = "<64 hex characters>"
+    /// address = "200:..."
+    /// subnet = "300:...::/64"
+    /// ```
+    ///
+    /// The output contains the secret key, so it should be handled like a key file.
+    pub fn to_toml(&self) -> Result<Zeroizing<String>, TomlError> {
+        audit(self, KeyOperation::Export, "NodeIdentity::to_toml");
+        let document = IdentityDocument {
+            identity: IdentityTable {
+                secret_key: Zeroizing::new(self.hex_joined()),
+                public_key: hex::encode(self.signing_keys.verifying_key().as_bytes()),
+                address: Some(self.address()),
+                subnet: Some(self.subnet()),
+            },
+        };
+        Ok(Zeroizing::new(toml::to_string(&document)?))
+    }
+
+    /// Parse the `[identity]` table of a TOML config, as written by [`NodeIdentity::to_toml`].
+    ///
+    /// Other tables and keys in the document are ignored, so whole config files can be passed
+    /// in. The public key, address and subnet have to match the secret key; the address and
+    /// subnet may be left out.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::NodeIdentity;
+    ///
+    /// let node = NodeIdentity::new(&mut thread_rng());
+    /// let config = format!("listen = \"tls://[::]:443\"\n\n{}", *node.to_toml().unwrap());
+    /// let parsed = NodeIdentity::from_toml(&config).unwrap();
+    /// assert_eq!(parsed.address(), node.address());
+    /// ```
+    pub fn from_toml(toml: &str) -> Result<Self, TomlError> {
+        let document: IdentityDocument = toml::from_str(toml)?;
+        let table = document.identity;
+        let identity = Self::from_hex(&table.secret_key, Some(&table.public_key))
+            .map_err(TomlError::InvalidKey)?;
+        if table
+            .address
+            .is_some_and(|address| address != identity.address())
+            || table
+                .subnet
+                .is_some_and(|subnet| subnet != identity.subnet())
+        {
+            return Err(TomlError::WrongAddress);
+        }
+        Ok(identity)
+    }
+}
+
+impl PeerDb {
+    /// Serialize the database as an array of `[[peers]]` tables, for embedding into TOML configs.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::{NodeIdentity, PeerDb};
+    ///
+    /// let mut db = PeerDb::new();
+    /// db.observe(NodeIdentity::new(&mut thread_rng()).public_key().to_bytes());
+    /// let toml = db.to_toml().unwrap();
+    /// assert!(toml.starts_with("[[peers]]"));
+    /// assert_eq!(PeerDb::from_toml(&toml).unwrap(), db);
+    /// ```
+    pub fn to_toml(&self) -> Result<String, TomlError> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Parse a database from the `[[peers]]` tables of a TOML config, as written by
+    /// [`PeerDb::to_toml`].
+    pub fn from_toml(toml: &str) -> Result<Self, TomlError> {
+        let mut db: Self = toml::from_str(toml)?;
+        db.normalize();
+        Ok(db)
+    }
+}
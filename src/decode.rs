@@ -0,0 +1,129 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use core::convert::TryInto;
+
+use zeroize::Zeroize;
+
+use crate::{FromHexError, NodeIdentity, PublicKeyPolicy};
+
+/// Decode exactly `2 * N` hex characters into the buffer, zeroizing it on errors.
+fn decode_into<const N: usize>(hex: &str, buffer: &mut [u8; N]) -> Result<(), FromHexError> {
+    hex::decode_to_slice(hex, buffer).map_err(|error| {
+        buffer.zeroize();
+        FromHexError::from(error)
+    })
+}
+
+/// Decode 32 hex encoded bytes of a public key into the buffer.
+pub fn decode_public_key(hex: &str, buffer: &mut [u8; 32]) -> Result<(), FromHexError> {
+    if hex.len() != 64 {
+        return Err(FromHexError::WrongPublicKeyLength(hex.len()));
+    }
+    decode_into(hex, buffer)
+}
+
+/// Decode 32 hex encoded bytes of a secret key into the buffer.
+///
+/// The buffer is zeroized if decoding fails, so no partially decoded secret is left behind.
+pub fn decode_secret_key(hex: &str, buffer: &mut [u8; 32]) -> Result<(), FromHexError> {
+    if hex.len() != 64 {
+        return Err(FromHexError::WrongSecretKeyLength(hex.len()));
+    }
+    decode_into(hex, buffer)
+}
+
+/// Decode 64 hex encoded bytes of a keypair, the secret key followed by the public key, into the
+/// buffer.
+///
+/// The buffer is zeroized if decoding fails, so no partially decoded secret is left behind.
+pub fn decode_keypair(hex: &str, buffer: &mut [u8; 64]) -> Result<(), FromHexError> {
+    if hex.len() != 128 {
+        return Err(FromHexError::WrongSecretKeyLength(hex.len()));
+    }
+    decode_into(hex, buffer)
+}
+
+impl NodeIdentity {
+    /// Parses hexadecimally encoded keypairs like [`NodeIdentity::from_hex_with`], decoding the
+    /// keys into the caller-provided buffer instead of temporary ones.
+    ///
+    /// This never allocates, and the secret key is only ever decoded into `buffer`, which is
+    /// zeroized before returning, whether parsing succeeded or not. Callers can keep the buffer in
+    /// memory they control, e.g. pages locked against swapping, and reuse it for many keys.
+    ///
+    /// ```rust
+    /// use yggdrasil_keys::{NodeIdentity, PublicKeyPolicy};
+    ///
+    /// let mut buffer = [0u8; 64];
+    /// let secret = "fd0b1d05c8cf0e4ac5d2fdd76cf4bbcf2719352ed07e8ba11b4e6ab8b4e35b8b";
+    /// let node =
+    ///     NodeIdentity::from_hex_buffered(secret, None, PublicKeyPolicy::Verify, &mut buffer)
+    ///         .unwrap();
+    /// assert_eq!(buffer, [0u8; 64]);
+    /// ```
+    pub fn from_hex_buffered(
+        sec_hex: &str,
+        pub_hex: Option<&str>,
+        policy: PublicKeyPolicy,
+        buffer: &mut [u8; 64],
+    ) -> Result<Self, FromHexError> {
+        let result = Self::parse_buffered(sec_hex, pub_hex, policy, buffer);
+        buffer.zeroize();
+        result
+    }
+
+    fn parse_buffered(
+        sec_hex: &str,
+        pub_hex: Option<&str>,
+        policy: PublicKeyPolicy,
+        buffer: &mut [u8; 64],
+    ) -> Result<Self, FromHexError> {
+        let included_public = match sec_hex.len() {
+            128 => {
+                decode_keypair(sec_hex, buffer)?;
+                true
+            }
+            _ => {
+                let (secret, _) = buffer.split_at_mut(32);
+                decode_secret_key(sec_hex, secret.try_into().unwrap())?;
+                false
+            }
+        };
+        let (secret, public) = buffer.split_at_mut(32);
+        let public: &mut [u8; 32] = public.try_into().unwrap();
+        if let Some(pub_hex) = pub_hex {
+            let mut supplied = [0u8; 32];
+            decode_public_key(pub_hex, &mut supplied)?;
+            if included_public && supplied != *public {
+                return Err(FromHexError::ConflictingPubKeys);
+            }
+            *public = supplied;
+        }
+        let signing_keys = ed25519_dalek::SigningKey::from_bytes((&*secret).try_into().unwrap());
+        let public_supplied = included_public || pub_hex.is_some();
+        if policy == PublicKeyPolicy::Verify
+            && public_supplied
+            && signing_keys.verifying_key().as_bytes() != public
+        {
+            return Err(FromHexError::WrongPublicKey);
+        }
+        Ok(Self { signing_keys })
+    }
+}
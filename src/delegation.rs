@@ -0,0 +1,168 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::{Signature, Signer, VerifyingKey};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::net::Ipv6Addr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    audit::{audit, KeyOperation},
+    helper::address_bytes,
+    verify_signature, DelegationError, NodeIdentity, VerificationMode,
+};
+
+/// Domain separation prefix for the signatures in [`ServiceDelegation`]s
+const DELEGATION_CONTEXT: &[u8] = b"yggdrasil-keys service delegation v1";
+
+/// A signed record delegating a service name to an address within the `/64` subnet of a node.
+///
+/// Nodes publish these so discovery systems can map service names like `www` or `matrix` to
+/// addresses, trusting the mapping because only the node key could have signed it. Service names
+/// are DNS labels: up to 63 lowercase ASCII letters, digits and hyphens. Records carry the time
+/// they were issued at, so newer records can replace older ones. With the `serde` feature, records
+/// can be (de)serialized.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{AddressVersion, NodeIdentity, ServiceDelegation};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let address = node.versioned_subnet(AddressVersion::V0_4).service_address("matrix");
+/// let record = ServiceDelegation::new(&node, "matrix", address).unwrap();
+/// assert_eq!(record.verify(), Ok(()));
+/// assert_eq!(record.address(), address);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ServiceDelegation {
+    service: String,
+    address: Ipv6Addr,
+    issued: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::helper::serde_hex"))]
+    public_key: [u8; 32],
+    #[cfg_attr(feature = "serde", serde(with = "crate::helper::serde_hex"))]
+    signature: [u8; 64],
+}
+
+impl ServiceDelegation {
+    /// Delegate a service to an address within the subnet of the node, issued now.
+    pub fn new(
+        identity: &NodeIdentity,
+        service: &str,
+        address: Ipv6Addr,
+    ) -> Result<Self, DelegationError> {
+        let issued = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        Self::new_at(identity, service, address, issued)
+    }
+
+    /// Delegate a service to an address within the subnet of the node, issued at the given unix
+    /// timestamp.
+    pub fn new_at(
+        identity: &NodeIdentity,
+        service: &str,
+        address: Ipv6Addr,
+        issued: u64,
+    ) -> Result<Self, DelegationError> {
+        let public_key = identity.signing_keys.verifying_key().to_bytes();
+        check(&public_key, service, address)?;
+        audit(identity, KeyOperation::Sign, "ServiceDelegation::new_at");
+        let signature =
+            identity
+                .signing_keys
+                .sign(&delegation_message(&public_key, service, address, issued));
+        Ok(Self {
+            service: service.to_owned(),
+            address,
+            issued,
+            public_key,
+            signature: signature.to_bytes(),
+        })
+    }
+
+    /// The delegated service name
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// The address the service is delegated to
+    pub fn address(&self) -> Ipv6Addr {
+        self.address
+    }
+
+    /// When the record was issued, as a unix timestamp
+    pub fn issued(&self) -> u64 {
+        self.issued
+    }
+
+    /// The ed25519 public key of the delegating node
+    pub fn public_key(&self) -> &[u8; 32] {
+        &self.public_key
+    }
+
+    /// Check that the record was signed by the node key, and the address lies within the node's
+    /// subnet.
+    pub fn verify(&self) -> Result<(), DelegationError> {
+        check(&self.public_key, &self.service, self.address)?;
+        verify_signature(
+            &VerifyingKey::from_bytes(&self.public_key)?,
+            &delegation_message(&self.public_key, &self.service, self.address, self.issued),
+            &Signature::from_bytes(&self.signature),
+            VerificationMode::Strict,
+        )?;
+        Ok(())
+    }
+}
+
+/// Check the service name, and that the address lies in the subnet of the public key.
+fn check(public_key: &[u8; 32], service: &str, address: Ipv6Addr) -> Result<(), DelegationError> {
+    let valid_label = (1..=63).contains(&service.len())
+        && !service.starts_with('-')
+        && !service.ends_with('-')
+        && service
+            .bytes()
+            .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'-');
+    if !valid_label {
+        return Err(DelegationError::InvalidServiceName);
+    }
+    let subnet = address_bytes(public_key, &NodeIdentity::IP_PREFIX, true);
+    if address.octets()[0..8] != subnet[0..8] {
+        return Err(DelegationError::OutsideSubnet);
+    }
+    Ok(())
+}
+
+fn delegation_message(
+    public_key: &[u8; 32],
+    service: &str,
+    address: Ipv6Addr,
+    issued: u64,
+) -> Vec<u8> {
+    [
+        DELEGATION_CONTEXT,
+        public_key,
+        &address.octets(),
+        &issued.to_be_bytes(),
+        service.as_bytes(),
+    ]
+    .concat()
+}
@@ -0,0 +1,191 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::VerifyingKey;
+#[cfg(feature = "ipnet")]
+use ipnet::Ipv6Net;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::net::Ipv6Addr;
+
+use crate::{KeyFile, NodeIdentity, PeerDb, PublicNodeIdentity};
+
+/// Something that can be compared with [`diff`], like identities, key files or peer databases.
+///
+/// Every part is optional, as not everything carries a key, a comment or peers. Parts are only
+/// compared if both sides have them.
+pub trait Diffable {
+    /// The node key
+    fn public_key(&self) -> Option<VerifyingKey> {
+        None
+    }
+
+    /// A comment describing the key
+    fn comment(&self) -> Option<&str> {
+        None
+    }
+
+    /// The public keys of known peers
+    fn peers(&self) -> Option<BTreeSet<[u8; 32]>> {
+        None
+    }
+}
+
+impl Diffable for NodeIdentity {
+    fn public_key(&self) -> Option<VerifyingKey> {
+        Some(NodeIdentity::public_key(self))
+    }
+}
+
+impl Diffable for VerifyingKey {
+    fn public_key(&self) -> Option<VerifyingKey> {
+        Some(*self)
+    }
+}
+
+impl Diffable for KeyFile {
+    fn public_key(&self) -> Option<VerifyingKey> {
+        Some(self.identity().public_key())
+    }
+
+    fn comment(&self) -> Option<&str> {
+        Some(KeyFile::comment(self))
+    }
+}
+
+impl Diffable for PeerDb {
+    fn peers(&self) -> Option<BTreeSet<[u8; 32]>> {
+        Some(self.iter().map(|record| record.public_key).collect())
+    }
+}
+
+/// A value that differs between the old and new version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Changed<T> {
+    /// The value before the change
+    pub old: T,
+    /// The value after the change
+    pub new: T,
+}
+
+impl<T: PartialEq> Changed<T> {
+    fn between(old: T, new: T) -> Option<Self> {
+        (old != new).then_some(Self { old, new })
+    }
+}
+
+/// What changed between two versions of an identity, key file or peer database, see [`diff`].
+///
+/// The report is displayed as one line per change, for audit logs:
+///
+/// ```text
+/// key: <old hex> -> <new hex>
+/// address: 200:... -> 201:...
+/// subnet: 300:...::/64 -> 301:...::/64
+/// comment: "laptop" -> "old laptop"
+/// peer added: <hex>
+/// peer removed: <hex>
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiffReport {
+    /// The node key
+    pub key: Option<Changed<VerifyingKey>>,
+    /// The address derived from the node key
+    pub address: Option<Changed<Ipv6Addr>>,
+    /// The `/64` subnet derived from the node key
+    #[cfg(feature = "ipnet")]
+    pub subnet: Option<Changed<Ipv6Net>>,
+    /// The comment describing the key
+    pub comment: Option<Changed<String>>,
+    /// Peers only known to the new version, ordered by public key
+    pub added_peers: Vec<[u8; 32]>,
+    /// Peers only known to the old version, ordered by public key
+    pub removed_peers: Vec<[u8; 32]>,
+}
+
+impl DiffReport {
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(key) = &self.key {
+            writeln!(
+                f,
+                "key: {} -> {}",
+                hex::encode(key.old.as_bytes()),
+                hex::encode(key.new.as_bytes())
+            )?;
+        }
+        if let Some(address) = &self.address {
+            writeln!(f, "address: {} -> {}", address.old, address.new)?;
+        }
+        #[cfg(feature = "ipnet")]
+        if let Some(subnet) = &self.subnet {
+            writeln!(f, "subnet: {} -> {}", subnet.old, subnet.new)?;
+        }
+        if let Some(comment) = &self.comment {
+            writeln!(f, "comment: {:?} -> {:?}", comment.old, comment.new)?;
+        }
+        for peer in &self.added_peers {
+            writeln!(f, "peer added: {}", hex::encode(peer))?;
+        }
+        for peer in &self.removed_peers {
+            writeln!(f, "peer removed: {}", hex::encode(peer))?;
+        }
+        Ok(())
+    }
+}
+
+/// Report what changed between two versions of an identity, key file or peer database.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{diff, KeyFile, NodeIdentity};
+///
+/// let old = KeyFile::new(NodeIdentity::new(&mut thread_rng()), "laptop").unwrap();
+/// let new = KeyFile::new(NodeIdentity::new(&mut thread_rng()), "laptop").unwrap();
+/// let report = diff(&old, &new);
+/// assert!(report.key.is_some());
+/// assert!(report.comment.is_none());
+/// assert!(diff(&old, &old).is_empty());
+/// ```
+pub fn diff<T: Diffable + ?Sized>(old: &T, new: &T) -> DiffReport {
+    let mut report = DiffReport::default();
+    if let (Some(old), Some(new)) = (old.public_key(), new.public_key()) {
+        report.key = Changed::between(old, new);
+        let (old, new) = (PublicNodeIdentity::from(old), PublicNodeIdentity::from(new));
+        report.address = Changed::between(old.address(), new.address());
+        #[cfg(feature = "ipnet")]
+        {
+            report.subnet = Changed::between(old.subnet(), new.subnet());
+        }
+    }
+    if let (Some(old), Some(new)) = (old.comment(), new.comment()) {
+        report.comment = Changed::between(old.to_owned(), new.to_owned());
+    }
+    if let (Some(old), Some(new)) = (old.peers(), new.peers()) {
+        report.added_peers = new.difference(&old).copied().collect();
+        report.removed_peers = old.difference(&new).copied().collect();
+    }
+    report
+}
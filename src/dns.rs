@@ -0,0 +1,181 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use std::fmt;
+use std::net::Ipv6Addr;
+
+#[cfg(feature = "ipnet")]
+use ipnet::Ipv6Net;
+
+use crate::{helper::address_bytes, NodeIdentity};
+
+/// A DNS `AAAA` record pointing a hostname at a node address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AaaaRecord {
+    /// The owner name of the record, either relative to the zone origin or fully qualified
+    pub name: String,
+    /// The node address derived from the public key
+    pub address: Ipv6Addr,
+}
+
+impl AaaaRecord {
+    /// Create a record for the node with the given ed25519 public key,
+    /// using the default IP prefix.
+    pub fn new<N: Into<String>>(name: N, public_key: &[u8; 32]) -> Self {
+        let address = Ipv6Addr::from(address_bytes(public_key, &NodeIdentity::IP_PREFIX, false));
+        Self {
+            name: name.into(),
+            address,
+        }
+    }
+}
+
+/// Formats the record as a BIND-style zone file line.
+impl fmt::Display for AaaaRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\tIN\tAAAA\t{}", self.name, self.address)
+    }
+}
+
+/// Create `AAAA` records for a list of (hostname, public key) pairs.
+pub fn aaaa_records<I, N>(nodes: I) -> Vec<AaaaRecord>
+where
+    I: IntoIterator<Item = (N, [u8; 32])>,
+    N: Into<String>,
+{
+    nodes
+        .into_iter()
+        .map(|(name, public_key)| AaaaRecord::new(name, &public_key))
+        .collect()
+}
+
+/// A DNS `PTR` record pointing the reverse name of a node address back at its hostname.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PtrRecord {
+    /// The fully qualified `ip6.arpa` name of the node address
+    pub name: String,
+    /// The hostname the address points back to
+    pub target: String,
+}
+
+impl PtrRecord {
+    /// Create a record for the node with the given ed25519 public key,
+    /// using the default IP prefix.
+    ///
+    /// The target should be fully qualified (end with a dot), as it is written out verbatim.
+    pub fn new<N: Into<String>>(target: N, public_key: &[u8; 32]) -> Self {
+        let address = Ipv6Addr::from(address_bytes(public_key, &NodeIdentity::IP_PREFIX, false));
+        Self {
+            name: reverse_name(address),
+            target: target.into(),
+        }
+    }
+}
+
+/// Formats the record as a BIND-style zone file line.
+impl fmt::Display for PtrRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\tIN\tPTR\t{}", self.name, self.target)
+    }
+}
+
+/// Create `PTR` records for a list of (hostname, public key) pairs.
+pub fn ptr_records<I, N>(nodes: I) -> Vec<PtrRecord>
+where
+    I: IntoIterator<Item = (N, [u8; 32])>,
+    N: Into<String>,
+{
+    nodes
+        .into_iter()
+        .map(|(target, public_key)| PtrRecord::new(target, &public_key))
+        .collect()
+}
+
+/// The fully qualified `ip6.arpa` name of an address.
+pub fn reverse_name(address: Ipv6Addr) -> String {
+    nibble_name(&address.octets(), 32)
+}
+
+/// The `ip6.arpa` zones covering a network.
+///
+/// Reverse zones can only be cut at nibble boundaries, so networks with a prefix length that
+/// isn't a multiple of four are covered by several zones: `200::/7` results in `2.0.ip6.arpa.`
+/// and `3.0.ip6.arpa.`, while a node's `/64` subnet results in a single zone.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{reverse_name, reverse_zones, NodeIdentity};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let zones = reverse_zones(node.subnet());
+/// assert_eq!(zones.len(), 1);
+/// assert!(reverse_name(node.subnet().addr()).ends_with(&zones[0]));
+/// ```
+#[cfg(feature = "ipnet")]
+pub fn reverse_zones(net: Ipv6Net) -> Vec<String> {
+    let net = net.trunc();
+    let nibbles = (net.prefix_len() as usize).div_ceil(4);
+    let extra_bits = nibbles * 4 - net.prefix_len() as usize;
+    let octets = net.network().octets();
+    (0..(1u8 << extra_bits))
+        .map(|i| {
+            let mut octets = octets;
+            if nibbles > 0 {
+                let index = (nibbles - 1) / 2;
+                let shift = if nibbles % 2 == 1 { 4 } else { 0 };
+                octets[index] |= i << shift;
+            }
+            nibble_name(&octets, nibbles)
+        })
+        .collect()
+}
+
+/// The `ip6.arpa` name consisting of the first `nibbles` nibbles of the address.
+fn nibble_name(octets: &[u8; 16], nibbles: usize) -> String {
+    let mut name = String::with_capacity(nibbles * 2 + 9);
+    for i in (0..nibbles).rev() {
+        let nibble = if i % 2 == 0 {
+            octets[i / 2] >> 4
+        } else {
+            octets[i / 2] & 0x0f
+        };
+        name.push(char::from_digit(nibble as u32, 16).unwrap());
+        name.push('.');
+    }
+    name.push_str("ip6.arpa.");
+    name
+}
+
+/// Render records as a zone file fragment, one record per line.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{aaaa_records, zone_fragment, NodeIdentity};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let public_key = *node.public_key().as_bytes();
+/// let zone = zone_fragment(&aaaa_records(vec![("node1", public_key)]));
+/// assert_eq!(zone, format!("node1\tIN\tAAAA\t{}\n", node.address()));
+/// ```
+pub fn zone_fragment<R: fmt::Display>(records: &[R]) -> String {
+    records
+        .iter()
+        .map(|record| format!("{}\n", record))
+        .collect()
+}
@@ -0,0 +1,86 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::VerifyingKey;
+use hkdf::Hkdf;
+use sha2::Sha512;
+use zeroize::Zeroizing;
+
+use crate::{
+    audit::{audit, KeyOperation},
+    KeyExchangeError, NodeIdentity,
+};
+
+/// HKDF info string prefix used to derive shared secrets between node identities
+const ECDH_INFO: &[u8] = b"yggdrasil-keys v1 ecdh ";
+
+impl NodeIdentity {
+    /// Derive a symmetric key shared with a peer, bound to both node identities and a context.
+    ///
+    /// Both keys are converted to X25519, and the result of the key exchange is expanded with
+    /// HKDF-SHA512. The info string contains both public keys in ascending order followed by the
+    /// context, so both sides derive the same key, and different contexts result in unrelated
+    /// keys. Weak (small order) peer keys are rejected, as they would result in a predictable
+    /// secret.
+    ///
+    /// The secret is static for a pair of identities and a context, so it's best used to
+    /// authenticate an ephemeral key exchange rather than encrypting data directly. Using the
+    /// signing keys for key exchange is safe as long as the two uses are kept apart, see
+    /// [On using the same key pair for Ed25519 and an X25519 based KEM](https://eprint.iacr.org/2021/509).
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::NodeIdentity;
+    ///
+    /// let alice = NodeIdentity::new(&mut thread_rng());
+    /// let bob = NodeIdentity::new(&mut thread_rng());
+    /// let a = alice.shared_secret(&bob.public_key(), b"example").unwrap();
+    /// let b = bob.shared_secret(&alice.public_key(), b"example").unwrap();
+    /// assert_eq!(*a, *b);
+    /// ```
+    pub fn shared_secret(
+        &self,
+        peer: &VerifyingKey,
+        context: &[u8],
+    ) -> Result<Zeroizing<[u8; 32]>, KeyExchangeError> {
+        if peer.is_weak() {
+            return Err(KeyExchangeError::WeakPeerKey);
+        }
+        audit(
+            self,
+            KeyOperation::KeyExchange,
+            "NodeIdentity::shared_secret",
+        );
+        let scalar = Zeroizing::new(self.signing_keys.to_scalar_bytes());
+        let shared = Zeroizing::new(peer.to_montgomery().mul_clamped(*scalar).to_bytes());
+
+        let own = self.signing_keys.verifying_key().to_bytes();
+        let peer = peer.to_bytes();
+        let (low, high) = if own <= peer {
+            (&own, &peer)
+        } else {
+            (&peer, &own)
+        };
+        let hkdf = Hkdf::<Sha512>::new(None, &shared[..]);
+        let mut key = Zeroizing::new([0u8; 32]);
+        hkdf.expand_multi_info(&[ECDH_INFO, low, high, context], &mut key[..])
+            .unwrap();
+        Ok(key)
+    }
+}
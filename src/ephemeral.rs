@@ -0,0 +1,108 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::{Signature, SignatureError, Signer, VerifyingKey};
+#[cfg(feature = "rand")]
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audit::{audit, KeyOperation},
+    verify_signature, NodeIdentity, VerificationMode,
+};
+
+/// Domain separation prefix for the signatures in [`LinkageProof`]s
+const LINKAGE_CONTEXT: &[u8] = b"yggdrasil-keys ephemeral linkage v1";
+
+/// Proof that an ephemeral identity belongs to a long-term identity.
+///
+/// The proof is a signature by the long-term key over the ephemeral public key. As long as the
+/// proof is kept private, the ephemeral identity can't be linked to the long-term identity, but
+/// revealing it later proves ownership of the ephemeral identity.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LinkageProof {
+    #[cfg_attr(feature = "serde", serde(with = "crate::helper::serde_hex"))]
+    long_term: [u8; 32],
+    #[cfg_attr(feature = "serde", serde(with = "crate::helper::serde_hex"))]
+    ephemeral: [u8; 32],
+    #[cfg_attr(feature = "serde", serde(with = "crate::helper::serde_hex"))]
+    signature: [u8; 64],
+}
+
+impl LinkageProof {
+    /// Create a proof that the ephemeral identity belongs to the long-term identity.
+    pub fn new(long_term: &NodeIdentity, ephemeral: &NodeIdentity) -> Self {
+        let long_term_key = long_term.signing_keys.verifying_key().to_bytes();
+        let ephemeral_key = ephemeral.signing_keys.verifying_key().to_bytes();
+        audit(long_term, KeyOperation::Sign, "LinkageProof::new");
+        let signature = long_term
+            .signing_keys
+            .sign(&linkage_message(&long_term_key, &ephemeral_key));
+        Self {
+            long_term: long_term_key,
+            ephemeral: ephemeral_key,
+            signature: signature.to_bytes(),
+        }
+    }
+
+    /// The ed25519 public key of the long-term identity
+    pub fn long_term_key(&self) -> &[u8; 32] {
+        &self.long_term
+    }
+
+    /// The ed25519 public key of the ephemeral identity
+    pub fn ephemeral_key(&self) -> &[u8; 32] {
+        &self.ephemeral
+    }
+
+    /// Check that the proof was signed by the long-term key.
+    pub fn verify(&self) -> Result<(), SignatureError> {
+        let public_key = VerifyingKey::from_bytes(&self.long_term)?;
+        verify_signature(
+            &public_key,
+            &linkage_message(&self.long_term, &self.ephemeral),
+            &Signature::from_bytes(&self.signature),
+            VerificationMode::Strict,
+        )
+    }
+}
+
+impl NodeIdentity {
+    /// Generate a throwaway identity, together with a proof linking it to this identity.
+    ///
+    /// The ephemeral identity is independent of this one. Keep the [`LinkageProof`] private until
+    /// ownership of the ephemeral identity needs to be proven.
+    ///
+    /// Requires the `rand` feature.
+    #[cfg(feature = "rand")]
+    pub fn ephemeral<R: CryptoRng + RngCore>(
+        &self,
+        csprng: &mut R,
+    ) -> (NodeIdentity, LinkageProof) {
+        let ephemeral = NodeIdentity::new(csprng);
+        let proof = LinkageProof::new(self, &ephemeral);
+        (ephemeral, proof)
+    }
+}
+
+fn linkage_message(long_term: &[u8; 32], ephemeral: &[u8; 32]) -> Vec<u8> {
+    [LINKAGE_CONTEXT, long_term, ephemeral].concat()
+}
@@ -18,26 +18,1490 @@
  *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
  ********************************************************************************/
 
+#[cfg(feature = "std")]
+use std::sync::Arc;
 use thiserror::Error;
 
+// Many errors wrap IO errors or errors from other crates, like serde_json or the ed25519
+// implementation, which don't implement `PartialEq`. The manual `PartialEq` impls below compare
+// those by their messages, using `eq_by_message`.
+
+/// Compare two errors by their messages.
+#[cfg(feature = "std")]
+fn eq_by_message<E: core::fmt::Display>(a: &E, b: &E) -> bool {
+    a.to_string() == b.to_string()
+}
+
+/// Stable codes identifying the cause of an error.
+///
+/// Every error variant in this crate maps to exactly one code, and the numeric values of existing
+/// codes never change, so they can be used across FFI boundaries and persisted in logs.
+#[non_exhaustive]
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// See [`FromHexError::WrongSecretKeyLength`]
+    WrongSecretKeyLength = 1,
+    /// See [`FromHexError::WrongPublicKeyLength`]
+    WrongPublicKeyLength = 2,
+    /// See [`FromHexError::Hex`]
+    InvalidHex = 3,
+    /// See [`FromHexError::ConflictingPubKeys`]
+    ConflictingPubKeys = 4,
+    /// See [`FromHexError::InvalidSigKey`]
+    InvalidSigKey = 5,
+    /// See [`SignatureEncodingError::InvalidR`]
+    InvalidSignatureR = 6,
+    /// See [`SignatureEncodingError::NonCanonicalR`]
+    NonCanonicalSignatureR = 7,
+    /// See [`SignatureEncodingError::SmallOrderR`]
+    SmallOrderSignatureR = 8,
+    /// See [`SignatureEncodingError::NonCanonicalS`]
+    NonCanonicalSignatureS = 9,
+    /// See `SignedError::Serialization` and `PeerDbError::Serialization`
+    Serialization = 10,
+    /// See `SignedError::InvalidSignature`, `HttpSignatureError::InvalidSignature`,
+    /// `JsonSignatureError::InvalidSignature`, `DelegationError::InvalidSignature` and
+    /// `StreamError::InvalidSignature`
+    InvalidSignature = 11,
+    /// See `Pkcs12Error::Pkcs12`
+    InvalidPkcs12 = 12,
+    /// See `Pkcs12Error::NoEd25519Key`
+    NoEd25519Key = 13,
+    /// See `Pkcs12Error::InvalidKey` and `MigrationError::InvalidPkcs8Key`
+    InvalidPkcs8Key = 14,
+    /// See [`SubmissionError::WrongWork`]
+    WrongWork = 15,
+    /// See [`SubmissionError::TooWeak`]
+    TooWeak = 16,
+    /// See [`SubmissionError::InvalidProof`]
+    InvalidProof = 17,
+    /// See [`FromHexError::WrongPublicKey`], [`FlashError::WrongPublicKey`] and
+    /// [`ValidationError::WrongPublicKey`]
+    WrongPublicKey = 18,
+    /// See `KeyFileError::Io`, `PeerDbError::Io`, `ArchiveError::Io`, `MigrationError::Io` and
+    /// `StreamError::Io`
+    Io = 19,
+    /// See `KeyFileError::Malformed`
+    MalformedKeyFile = 20,
+    /// See `KeyFileError::UnsupportedVersion`
+    UnsupportedKeyFileVersion = 21,
+    /// See `KeyFileError::InvalidKey`
+    InvalidKeyFileKey = 22,
+    /// See `KeyFileError::InvalidSelfSignature` and `ArchiveError::InvalidSelfSignature`
+    InvalidSelfSignature = 23,
+    /// See `KeyFileError::InvalidComment` and `ArchiveError::InvalidComment`
+    InvalidComment = 24,
+    /// See `KeyFileError::InsecurePermissions`
+    InsecurePermissions = 25,
+    /// See [`FlashError::BufferTooSmall`] and [`BitsError::BufferTooSmall`]
+    BufferTooSmall = 26,
+    /// See [`FlashError::Truncated`]
+    Truncated = 27,
+    /// See [`FlashError::BadMagic`]
+    BadMagic = 28,
+    /// See [`FlashError::UnsupportedVersion`]
+    UnsupportedFlashVersion = 29,
+    /// See [`FlashError::UnknownFlags`]
+    UnknownFlags = 30,
+    /// See [`KeyExchangeError::WeakPeerKey`]
+    WeakPeerKey = 31,
+    /// See `HttpAuthError::MissingConnectInfo`
+    MissingConnectInfo = 32,
+    /// See `HttpAuthError::MissingHeader`
+    MissingAuthHeader = 33,
+    /// See `HttpAuthError::MalformedHeader`
+    MalformedAuthHeader = 34,
+    /// See `HttpAuthError::Expired`
+    ExpiredAuthHeader = 35,
+    /// See `HttpAuthError::WrongAddress`
+    WrongSourceAddress = 36,
+    /// See `HttpAuthError::InvalidSignature`
+    InvalidAuthSignature = 37,
+    /// See `HttpSignatureError::MissingComponent`
+    MissingComponent = 38,
+    /// See `HttpSignatureError::MissingSignature` and `JsonSignatureError::MissingSignature`
+    MissingSignature = 39,
+    /// See `HttpSignatureError::MalformedSignature` and `JsonSignatureError::MalformedSignature`
+    MalformedSignature = 40,
+    /// See `HttpSignatureError::UnsupportedAlgorithm`
+    UnsupportedAlgorithm = 41,
+    /// See `JsonSignatureError::NotAnObject`
+    NotAnObject = 42,
+    /// See `JsonSignatureError::NonCanonicalNumber`
+    NonCanonicalNumber = 43,
+    /// See `SshCertificateError::Ssh`
+    Ssh = 44,
+    /// See `SshCertificateError::InvalidCertificate`
+    InvalidCertificate = 45,
+    /// See `SshCertificateError::WrongCertificateType`
+    WrongCertificateType = 46,
+    /// See `SshCertificateError::WrongPrincipal`
+    WrongPrincipal = 47,
+    /// See `OnionError::Malformed`
+    MalformedOnionAddress = 48,
+    /// See `OnionError::UnsupportedVersion`
+    UnsupportedOnionVersion = 49,
+    /// See `OnionError::WrongChecksum`
+    WrongOnionChecksum = 50,
+    /// See `OnionError::InvalidKey`
+    InvalidOnionKey = 51,
+    /// See `CjdnsError::MalformedKey`
+    MalformedCjdnsKey = 52,
+    /// See `ParseError::TooLong`
+    InputTooLong = 53,
+    /// See `ParseError::UnknownFormat`
+    UnknownKeyFormat = 54,
+    /// See `ParseError::InvalidKey`
+    InvalidKey = 55,
+    /// See `ParseError::Encrypted` and `ArchiveError::Encrypted`
+    EncryptedKey = 56,
+    /// See `ParseError::MalformedPem`
+    MalformedPem = 57,
+    /// See `ParseError::DegenerateSecret`
+    DegenerateSecret = 58,
+    /// See `CertificateError::Malformed`
+    MalformedCertificate = 59,
+    /// See `CertificateError::NotEd25519`
+    NotEd25519Certificate = 60,
+    /// See `CertificateError::InvalidKey`
+    InvalidCertificateKey = 61,
+    /// See `CertificateError::InvalidSelfSignature`
+    InvalidCertificateSignature = 62,
+    /// See `TomlError::Serialization`
+    TomlSerialization = 63,
+    /// See `TomlError::Malformed`
+    MalformedToml = 64,
+    /// See `TomlError::InvalidKey`
+    InvalidTomlKey = 65,
+    /// See `TomlError::WrongAddress`
+    WrongTomlAddress = 66,
+    /// See `ArchiveError::Malformed`
+    MalformedArchive = 67,
+    /// See `ArchiveError::UnsupportedVersion`
+    UnsupportedArchiveVersion = 68,
+    /// See `ArchiveError::InvalidKey`
+    InvalidArchiveKey = 69,
+    /// See `ArchiveError::InvalidLabel`
+    InvalidLabel = 70,
+    /// See `ArchiveError::DuplicateLabel`
+    DuplicateLabel = 71,
+    /// See `ArchiveError::UnknownLabel`
+    UnknownLabel = 72,
+    /// See `ArchiveError::Decryption`
+    ArchiveDecryption = 73,
+    /// See `MigrationError::MissingSetting`
+    MissingSetting = 74,
+    /// See `MigrationError::DuplicateSetting`
+    DuplicateSetting = 75,
+    /// See `MigrationError::ConflictingSettings`
+    ConflictingSettings = 76,
+    /// See `MigrationError::InvalidKey`
+    InvalidConfigKey = 77,
+    /// See `MigrationError::NonUnicodePath`
+    NonUnicodePath = 78,
+    /// See `PassphraseError::EmptyPassphrase`
+    EmptyPassphrase = 79,
+    /// See `PassphraseError::Argon2`
+    InvalidPassphraseParams = 80,
+    /// See `DelegationError::InvalidServiceName`
+    InvalidServiceName = 81,
+    /// See `DelegationError::OutsideSubnet`
+    OutsideSubnet = 82,
+    /// See `WordlistError::Empty`
+    EmptyWordlist = 83,
+    /// See `WordlistError::InvalidWord`
+    InvalidWord = 84,
+    /// See [`ValidationError::NonCanonicalPublicKey`], also used for
+    /// [`FromHexError::InvalidPublicKey`]
+    NonCanonicalPublicKey = 85,
+    /// See [`ValidationError::WeakPublicKey`], also used for [`FromHexError::InvalidPublicKey`]
+    WeakPublicKey = 86,
+    /// See [`PrefixError::Empty`]
+    EmptyPrefix = 87,
+    /// See [`PrefixError::TooLong`]
+    PrefixTooLong = 88,
+    /// See [`PrefixError::InvalidCidr`]
+    InvalidCidr = 89,
+    /// See [`PrefixError::UnalignedLength`]
+    UnalignedPrefixLength = 90,
+    /// See [`PrefixError::HostBitsSet`]
+    PrefixHostBitsSet = 91,
+    /// See [`FingerprintError::UnsupportedAlgorithm`]
+    UnsupportedFingerprintAlgorithm = 92,
+    /// See [`FingerprintError::InvalidEncoding`]
+    InvalidFingerprintEncoding = 93,
+    /// See [`AddressInfoError::OutsideNetwork`]
+    OutsideNetwork = 94,
+    /// See [`AddressInfoError::NotSubnet`]
+    NotSubnet = 95,
+    /// See [`AddressInfoError::SubnetPrefixLength`]
+    SubnetPrefixLength = 96,
+    /// See `KeyFileError::NotOwned`
+    KeyFileNotOwned = 97,
+}
+
 /// Describe error for trying to decode yggdrasil keys from hex strings.
-#[derive(Error, Debug)]
+#[cfg(feature = "hex")]
+#[derive(Error, Debug, Clone)]
+#[non_exhaustive]
 pub enum FromHexError {
     /// The `sec_hex` parameters can be either 64 hex encoded bytes,
     /// if they are a keypair,
     /// or 32 hex encoded bytes if they are just the private key.
+    /// Contains the length of the supplied string.
+    #[error("secret key has wrong length: expected 64 or 128 hex characters, got {0}")]
+    WrongSecretKeyLength(usize),
     /// The `pub_hex` parameters have to be 32 hex encoded bytes.
-    #[error("key has wrong length")]
-    WrongKeyLength,
+    /// Contains the length of the supplied string.
+    #[error("public key has wrong length: expected 64 hex characters, got {0}")]
+    WrongPublicKeyLength(usize),
     /// The strings have to be valid hex.
     #[error("string is not valid hex: {0}")]
-    Hex(#[from] hex::FromHexError),
+    Hex(#[cfg_attr(feature = "std", source)] hex::FromHexError),
     /// If `pub_hex` is `Some` and `sec_hex` contains a keypair,
     /// both supplied public keys have to be the same.
     #[error("pub keys in optional argument and included with secret key differ")]
     ConflictingPubKeys,
+    /// Supplied public keys have to belong to the secret key,
+    /// unless [`crate::PublicKeyPolicy::Trust`] is used.
+    #[error("the public key does not belong to the secret key")]
+    WrongPublicKey,
+    /// Public keys parsed on their own have to be canonically encoded and of large order,
+    /// see [`crate::PublicNodeIdentity::validate`].
+    #[error("the public key is invalid: {0}")]
+    InvalidPublicKey(#[cfg_attr(feature = "std", source)] ValidationError),
     /// The signing keys are checked by the ed25519 implementation after parsing.
     /// If something doesn't add up, this error will be returned.
+    #[cfg(feature = "std")]
     #[error("the signature keys are invalid: {0}")]
-    InvalidSigKey(#[from] ed25519_dalek::SignatureError),
+    InvalidSigKey(#[source] Arc<ed25519_dalek::SignatureError>),
+}
+
+#[cfg(feature = "hex")]
+impl FromHexError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::WrongSecretKeyLength(_) => ErrorCode::WrongSecretKeyLength,
+            Self::WrongPublicKeyLength(_) => ErrorCode::WrongPublicKeyLength,
+            Self::Hex(_) => ErrorCode::InvalidHex,
+            Self::ConflictingPubKeys => ErrorCode::ConflictingPubKeys,
+            Self::WrongPublicKey => ErrorCode::WrongPublicKey,
+            Self::InvalidPublicKey(error) => error.code(),
+            #[cfg(feature = "std")]
+            Self::InvalidSigKey(_) => ErrorCode::InvalidSigKey,
+        }
+    }
+}
+
+#[cfg(feature = "hex")]
+impl From<hex::FromHexError> for FromHexError {
+    fn from(error: hex::FromHexError) -> Self {
+        Self::Hex(error)
+    }
+}
+
+#[cfg(feature = "hex")]
+impl From<ValidationError> for FromHexError {
+    fn from(error: ValidationError) -> Self {
+        Self::InvalidPublicKey(error)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hex"))]
+impl From<ed25519_dalek::SignatureError> for FromHexError {
+    fn from(error: ed25519_dalek::SignatureError) -> Self {
+        Self::InvalidSigKey(Arc::new(error))
+    }
+}
+
+#[cfg(feature = "hex")]
+impl PartialEq for FromHexError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::WrongSecretKeyLength(a), Self::WrongSecretKeyLength(b)) => a == b,
+            (Self::WrongPublicKeyLength(a), Self::WrongPublicKeyLength(b)) => a == b,
+            (Self::Hex(a), Self::Hex(b)) => a == b,
+            (Self::ConflictingPubKeys, Self::ConflictingPubKeys) => true,
+            (Self::WrongPublicKey, Self::WrongPublicKey) => true,
+            (Self::InvalidPublicKey(a), Self::InvalidPublicKey(b)) => a == b,
+            #[cfg(feature = "std")]
+            (Self::InvalidSigKey(a), Self::InvalidSigKey(b)) => eq_by_message(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// Describe why a signature encoding was rejected by [`crate::check_signature_encoding`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureEncodingError {
+    /// The first 32 bytes of the signature have to encode a point on the curve.
+    #[error("R is not a valid curve point")]
+    InvalidR,
+    /// The point `R` has to be encoded canonically,
+    /// otherwise the same signature has several valid encodings.
+    #[error("R is not encoded canonically")]
+    NonCanonicalR,
+    /// The point `R` must not be of small order.
+    #[error("R is a small order point")]
+    SmallOrderR,
+    /// The scalar `S` has to be reduced modulo the group order,
+    /// otherwise the same signature has several valid encodings.
+    #[error("S is not reduced")]
+    NonCanonicalS,
+}
+
+impl SignatureEncodingError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::InvalidR => ErrorCode::InvalidSignatureR,
+            Self::NonCanonicalR => ErrorCode::NonCanonicalSignatureR,
+            Self::SmallOrderR => ErrorCode::SmallOrderSignatureR,
+            Self::NonCanonicalS => ErrorCode::NonCanonicalSignatureS,
+        }
+    }
+}
+
+/// Describe error for storing node identities with [`crate::NodeIdentity::to_flash`] and
+/// reading them with [`crate::NodeIdentity::from_flash`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashError {
+    /// The buffer has to be large enough for the image.
+    /// Contains the required size.
+    #[error("buffer too small: {0} bytes required")]
+    BufferTooSmall(usize),
+    /// The image has to be complete.
+    #[error("flash image is truncated")]
+    Truncated,
+    /// The image has to start with [`crate::FLASH_MAGIC`].
+    #[error("flash image does not start with the magic bytes")]
+    BadMagic,
+    /// Only known versions of the layout can be read.
+    /// Contains the version of the image.
+    #[error("flash layout version {0} is not supported")]
+    UnsupportedVersion(u8),
+    /// Images with flags that have to be understood, but aren't known, can't be read.
+    /// Contains the flags of the image.
+    #[error("flash image has unknown required flags: {0:#04x}")]
+    UnknownFlags(u8),
+    /// If the image contains the public key, it has to belong to the secret key.
+    #[error("the public key does not belong to the secret key")]
+    WrongPublicKey,
+}
+
+impl FlashError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::BufferTooSmall(_) => ErrorCode::BufferTooSmall,
+            Self::Truncated => ErrorCode::Truncated,
+            Self::BadMagic => ErrorCode::BadMagic,
+            Self::UnsupportedVersion(_) => ErrorCode::UnsupportedFlashVersion,
+            Self::UnknownFlags(_) => ErrorCode::UnknownFlags,
+            Self::WrongPublicKey => ErrorCode::WrongPublicKey,
+        }
+    }
+}
+
+/// Describe error for creating [`crate::Prefix`]es and deriving addresses and subnets with custom
+/// IP prefixes, like [`crate::NodeIdentity::try_address_with_prefix`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixError {
+    /// The prefix has to be at least one byte long.
+    #[error("the IP prefix is empty")]
+    Empty,
+    /// The prefix has to leave room for the derived part of the address or subnet.
+    #[error("the IP prefix is {length} bytes long, at most {max} bytes are supported")]
+    TooLong {
+        /// The length of the prefix
+        length: usize,
+        /// The maximum length for addresses or subnets
+        max: usize,
+    },
+    /// Prefixes have to be written as an IPv6 address, followed by a slash and the length.
+    #[error("the IP prefix is not in CIDR notation")]
+    InvalidCidr,
+    /// The length has to be one bit short of a multiple of eight, like `/7`.
+    /// Contains the length.
+    #[error("the IP prefix length /{0} is not one bit short of a whole byte")]
+    UnalignedLength(u8),
+    /// No bits may be set after the length of the prefix.
+    #[error("the IP prefix has bits set after its length")]
+    HostBitsSet,
+}
+
+impl PrefixError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Empty => ErrorCode::EmptyPrefix,
+            Self::TooLong { .. } => ErrorCode::PrefixTooLong,
+            Self::InvalidCidr => ErrorCode::InvalidCidr,
+            Self::UnalignedLength(_) => ErrorCode::UnalignedPrefixLength,
+            Self::HostBitsSet => ErrorCode::PrefixHostBitsSet,
+        }
+    }
+}
+
+/// Describe error for checking the consistency of keypairs with [`crate::NodeIdentity::validate`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The public key has to be the one derived from the secret key.
+    #[error("the public key does not belong to the secret key")]
+    WrongPublicKey,
+    /// The public key has to be the canonical encoding of a curve point.
+    #[error("the public key is not canonically encoded")]
+    NonCanonicalPublicKey,
+    /// The public key must not be of small order.
+    #[error("the public key is of small order")]
+    WeakPublicKey,
+}
+
+impl ValidationError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::WrongPublicKey => ErrorCode::WrongPublicKey,
+            Self::NonCanonicalPublicKey => ErrorCode::NonCanonicalPublicKey,
+            Self::WeakPublicKey => ErrorCode::WeakPublicKey,
+        }
+    }
+}
+
+/// Describe error for recovering key information from addresses with [`crate::AddressInfo`] and
+/// from subnets with `SubnetInfo`.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressInfoError {
+    /// The address has to be within the network it is decoded for.
+    #[error("the address is outside of the network")]
+    OutsideNetwork,
+    /// Subnets have to have the subnet flag set in their prefix.
+    #[error("the address is a node address, not a subnet")]
+    NotSubnet,
+    /// Subnets have to be `/64`s. Contains the prefix length.
+    #[error("the subnet is a /{0}, not a /64")]
+    SubnetPrefixLength(u8),
+}
+
+impl AddressInfoError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::OutsideNetwork => ErrorCode::OutsideNetwork,
+            Self::NotSubnet => ErrorCode::NotSubnet,
+            Self::SubnetPrefixLength(_) => ErrorCode::SubnetPrefixLength,
+        }
+    }
+}
+
+/// Describe error for the slice functions in [`crate::bits`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitsError {
+    /// The output buffer has to be at least as long as the input.
+    /// Contains the required size.
+    #[error("buffer too small: {0} bytes required")]
+    BufferTooSmall(usize),
+}
+
+impl BitsError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::BufferTooSmall(_) => ErrorCode::BufferTooSmall,
+        }
+    }
+}
+
+/// Describe error for parsing [`crate::Fingerprint`]s.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FingerprintError {
+    /// Only SHA256 fingerprints are supported, and they have to start with `SHA256:`.
+    #[error("the fingerprint does not start with \"SHA256:\"")]
+    UnsupportedAlgorithm,
+    /// The hash has to be 32 bytes of unpadded base64.
+    #[error("the fingerprint is not 32 bytes of unpadded base64")]
+    InvalidEncoding,
+}
+
+impl FingerprintError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::UnsupportedAlgorithm => ErrorCode::UnsupportedFingerprintAlgorithm,
+            Self::InvalidEncoding => ErrorCode::InvalidFingerprintEncoding,
+        }
+    }
+}
+
+/// Describe error for deriving shared secrets with [`crate::NodeIdentity::shared_secret`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyExchangeError {
+    /// The peer key must not be of small order.
+    #[error("the peer key is weak")]
+    WeakPeerKey,
+}
+
+impl KeyExchangeError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::WeakPeerKey => ErrorCode::WeakPeerKey,
+        }
+    }
+}
+
+/// Describe why a mining result was rejected by a [`crate::Coordinator`].
+#[cfg(feature = "std")]
+#[derive(Error, Debug, Clone)]
+pub enum SubmissionError {
+    /// The submission has to be for the work currently handed out by the coordinator.
+    #[error("submission is for a different work id")]
+    WrongWork,
+    /// The submitted key has to reach the minimum strength of the work.
+    #[error("key strength {strength} is below the minimum of {min_strength}")]
+    TooWeak {
+        /// The strength of the submitted key
+        strength: u32,
+        /// The minimum strength required by the work
+        min_strength: u32,
+    },
+    /// The proof has to be a valid signature by the submitted key.
+    #[error("the proof is invalid: {0}")]
+    InvalidProof(#[source] Arc<ed25519_dalek::SignatureError>),
+}
+
+#[cfg(feature = "std")]
+impl SubmissionError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::WrongWork => ErrorCode::WrongWork,
+            Self::TooWeak { .. } => ErrorCode::TooWeak,
+            Self::InvalidProof(_) => ErrorCode::InvalidProof,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ed25519_dalek::SignatureError> for SubmissionError {
+    fn from(error: ed25519_dalek::SignatureError) -> Self {
+        Self::InvalidProof(Arc::new(error))
+    }
+}
+
+#[cfg(feature = "std")]
+impl PartialEq for SubmissionError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::WrongWork, Self::WrongWork) => true,
+            (
+                Self::TooWeak {
+                    strength: a,
+                    min_strength: c,
+                },
+                Self::TooWeak {
+                    strength: b,
+                    min_strength: d,
+                },
+            ) => a == b && c == d,
+            (Self::InvalidProof(a), Self::InvalidProof(b)) => eq_by_message(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// Describe error for reading and writing [`crate::KeyFile`]s.
+#[cfg(all(feature = "std", feature = "hex"))]
+#[derive(Error, Debug, Clone)]
+pub enum KeyFileError {
+    /// The key file has to be readable, or writable and not existing yet.
+    #[error("key file could not be accessed: {0}")]
+    Io(#[source] Arc<std::io::Error>),
+    /// The key file has to follow the format exactly.
+    /// Contains a description of the problem.
+    #[error("key file is malformed: {0}")]
+    Malformed(&'static str),
+    /// Only known versions of the format can be read.
+    /// Contains the version of the file.
+    #[error("key file version {0} is not supported")]
+    UnsupportedVersion(u32),
+    /// The keys in the file have to be valid, and belong together.
+    #[error("key file contains invalid keys: {0}")]
+    InvalidKey(#[source] FromHexError),
+    /// The metadata has to be signed by the key in the file.
+    #[error("the self-signature is invalid: {0}")]
+    InvalidSelfSignature(#[source] Arc<ed25519_dalek::SignatureError>),
+    /// Comments have to fit on a single line.
+    #[error("comments must not contain line breaks")]
+    InvalidComment,
+    /// Secret key files must not be accessible by the group or others,
+    /// unless [`crate::PermissionCheck::Ignore`] is used.
+    /// Contains the permission bits of the file.
+    #[error("key file permissions {0:o} are too open")]
+    InsecurePermissions(u32),
+    /// Secret key files have to be owned by the current user,
+    /// unless [`crate::PermissionCheck::Ignore`] is used.
+    /// Contains the user id of the owner of the file.
+    #[error("key file is owned by user {0}, not the current user")]
+    NotOwned(u32),
+}
+
+#[cfg(all(feature = "std", feature = "hex"))]
+impl KeyFileError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Io(_) => ErrorCode::Io,
+            Self::Malformed(_) => ErrorCode::MalformedKeyFile,
+            Self::UnsupportedVersion(_) => ErrorCode::UnsupportedKeyFileVersion,
+            Self::InvalidKey(_) => ErrorCode::InvalidKeyFileKey,
+            Self::InvalidSelfSignature(_) => ErrorCode::InvalidSelfSignature,
+            Self::InvalidComment => ErrorCode::InvalidComment,
+            Self::InsecurePermissions(_) => ErrorCode::InsecurePermissions,
+            Self::NotOwned(_) => ErrorCode::KeyFileNotOwned,
+        }
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hex"))]
+impl From<std::io::Error> for KeyFileError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(Arc::new(error))
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hex"))]
+impl From<FromHexError> for KeyFileError {
+    fn from(error: FromHexError) -> Self {
+        Self::InvalidKey(error)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hex"))]
+impl From<ed25519_dalek::SignatureError> for KeyFileError {
+    fn from(error: ed25519_dalek::SignatureError) -> Self {
+        Self::InvalidSelfSignature(Arc::new(error))
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hex"))]
+impl PartialEq for KeyFileError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Io(a), Self::Io(b)) => eq_by_message(a, b),
+            (Self::Malformed(a), Self::Malformed(b)) => a == b,
+            (Self::UnsupportedVersion(a), Self::UnsupportedVersion(b)) => a == b,
+            (Self::InvalidKey(a), Self::InvalidKey(b)) => a == b,
+            (Self::InvalidSelfSignature(a), Self::InvalidSelfSignature(b)) => eq_by_message(a, b),
+            (Self::InvalidComment, Self::InvalidComment) => true,
+            (Self::InsecurePermissions(a), Self::InsecurePermissions(b)) => a == b,
+            (Self::NotOwned(a), Self::NotOwned(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Describe error for creating or verifying [`crate::Signed`] payloads.
+#[cfg(feature = "serde")]
+#[derive(Error, Debug, Clone)]
+pub enum SignedError {
+    /// The payload has to be serializable into JSON.
+    #[error("payload could not be serialized: {0}")]
+    Serialization(#[source] Arc<serde_json::Error>),
+    /// The public key of the signer has to be valid, and the signature has to match it.
+    #[error("the signature is invalid: {0}")]
+    InvalidSignature(#[source] Arc<ed25519_dalek::SignatureError>),
+}
+
+#[cfg(feature = "serde")]
+impl SignedError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Serialization(_) => ErrorCode::Serialization,
+            Self::InvalidSignature(_) => ErrorCode::InvalidSignature,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for SignedError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Serialization(Arc::new(error))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<ed25519_dalek::SignatureError> for SignedError {
+    fn from(error: ed25519_dalek::SignatureError) -> Self {
+        Self::InvalidSignature(Arc::new(error))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl PartialEq for SignedError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Serialization(a), Self::Serialization(b)) => eq_by_message(a, b),
+            (Self::InvalidSignature(a), Self::InvalidSignature(b)) => eq_by_message(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// Describe error for verifying signatures over streams with [`crate::verify_reader`].
+#[cfg(feature = "std")]
+#[derive(Error, Debug, Clone)]
+pub enum StreamError {
+    /// The input has to be readable until its end.
+    #[error("the input could not be read: {0}")]
+    Io(#[source] Arc<std::io::Error>),
+    /// The signature has to match the input and public key.
+    #[error("the signature is invalid: {0}")]
+    InvalidSignature(#[source] Arc<ed25519_dalek::SignatureError>),
+}
+
+#[cfg(feature = "std")]
+impl StreamError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Io(_) => ErrorCode::Io,
+            Self::InvalidSignature(_) => ErrorCode::InvalidSignature,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for StreamError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(Arc::new(error))
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ed25519_dalek::SignatureError> for StreamError {
+    fn from(error: ed25519_dalek::SignatureError) -> Self {
+        Self::InvalidSignature(Arc::new(error))
+    }
+}
+
+#[cfg(feature = "std")]
+impl PartialEq for StreamError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Io(a), Self::Io(b)) => eq_by_message(a, b),
+            (Self::InvalidSignature(a), Self::InvalidSignature(b)) => eq_by_message(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// Describe error for creating [`crate::Wordlist`]s.
+#[cfg(feature = "std")]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum WordlistError {
+    /// The wordlist has to contain at least one word.
+    #[error("the wordlist is empty")]
+    Empty,
+    /// Words have to consist of 1 to 30 hex digits.
+    /// Contains the word.
+    #[error("{0:?} is not a word of 1 to 30 hex digits")]
+    InvalidWord(String),
+}
+
+#[cfg(feature = "std")]
+impl WordlistError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Empty => ErrorCode::EmptyWordlist,
+            Self::InvalidWord(_) => ErrorCode::InvalidWord,
+        }
+    }
+}
+
+/// Describe error for creating and verifying [`crate::ServiceDelegation`]s.
+#[cfg(feature = "std")]
+#[derive(Error, Debug, Clone)]
+pub enum DelegationError {
+    /// Service names have to be DNS labels.
+    #[error("the service name is not a valid DNS label")]
+    InvalidServiceName,
+    /// Services can only be delegated to addresses in the subnet of the node.
+    #[error("the address is outside of the subnet of the node")]
+    OutsideSubnet,
+    /// The public key of the node has to be valid, and the signature has to match it.
+    #[error("the signature is invalid: {0}")]
+    InvalidSignature(#[source] Arc<ed25519_dalek::SignatureError>),
+}
+
+#[cfg(feature = "std")]
+impl DelegationError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::InvalidServiceName => ErrorCode::InvalidServiceName,
+            Self::OutsideSubnet => ErrorCode::OutsideSubnet,
+            Self::InvalidSignature(_) => ErrorCode::InvalidSignature,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ed25519_dalek::SignatureError> for DelegationError {
+    fn from(error: ed25519_dalek::SignatureError) -> Self {
+        Self::InvalidSignature(Arc::new(error))
+    }
+}
+
+#[cfg(feature = "std")]
+impl PartialEq for DelegationError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::InvalidServiceName, Self::InvalidServiceName) => true,
+            (Self::OutsideSubnet, Self::OutsideSubnet) => true,
+            (Self::InvalidSignature(a), Self::InvalidSignature(b)) => eq_by_message(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// Describe error for saving and loading [`crate::PeerDb`]s.
+#[cfg(feature = "serde")]
+#[derive(Error, Debug, Clone)]
+pub enum PeerDbError {
+    /// The database file has to be accessible.
+    #[error("peer database could not be accessed: {0}")]
+    Io(#[source] Arc<std::io::Error>),
+    /// The database file has to be valid JSON in the expected format.
+    #[error("peer database could not be (de)serialized: {0}")]
+    Serialization(#[source] Arc<serde_json::Error>),
+}
+
+#[cfg(feature = "serde")]
+impl PeerDbError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Io(_) => ErrorCode::Io,
+            Self::Serialization(_) => ErrorCode::Serialization,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<std::io::Error> for PeerDbError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(Arc::new(error))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for PeerDbError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Serialization(Arc::new(error))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl PartialEq for PeerDbError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Io(a), Self::Io(b)) => eq_by_message(a, b),
+            (Self::Serialization(a), Self::Serialization(b)) => eq_by_message(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// Describe why a request was rejected by [`crate::YggdrasilAuth`].
+#[cfg(feature = "axum")]
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpAuthError {
+    /// The server has to provide the source address of connections via `ConnectInfo`.
+    #[error("the source address of the connection is not available")]
+    MissingConnectInfo,
+    /// The request has to carry the authentication header.
+    #[error("the authentication header is missing")]
+    MissingHeader,
+    /// The header has to contain the public key, timestamp and signature, separated by colons.
+    #[error("the authentication header is malformed")]
+    MalformedHeader,
+    /// The timestamp in the header has to be close to the current time.
+    #[error("the authentication header is expired")]
+    Expired,
+    /// The request has to be sent from the address of the key in the header.
+    #[error("the request was not sent from the address of the key")]
+    WrongAddress,
+    /// The header has to be signed by the key for this request.
+    #[error("the signature in the authentication header is invalid")]
+    InvalidSignature,
+}
+
+#[cfg(feature = "axum")]
+impl HttpAuthError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::MissingConnectInfo => ErrorCode::MissingConnectInfo,
+            Self::MissingHeader => ErrorCode::MissingAuthHeader,
+            Self::MalformedHeader => ErrorCode::MalformedAuthHeader,
+            Self::Expired => ErrorCode::ExpiredAuthHeader,
+            Self::WrongAddress => ErrorCode::WrongSourceAddress,
+            Self::InvalidSignature => ErrorCode::InvalidAuthSignature,
+        }
+    }
+}
+
+/// Describe error for signing and verifying HTTP messages with [`crate::sign_request`] and
+/// [`crate::verify_request`].
+#[cfg(feature = "http-signatures")]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum HttpSignatureError {
+    /// The message has to contain every covered component.
+    /// Contains the name of the missing component.
+    #[error("the message does not contain the component {0}")]
+    MissingComponent(String),
+    /// The message has to contain a signature with the requested label.
+    /// Contains the label.
+    #[error("the message does not contain the signature {0}")]
+    MissingSignature(String),
+    /// The signature headers have to be valid structured fields.
+    #[error("the signature headers are malformed")]
+    MalformedSignature,
+    /// Only ed25519 signatures are supported.
+    #[error("the signature algorithm is not supported")]
+    UnsupportedAlgorithm,
+    /// The signature has to be made by the key over the covered components.
+    #[error("the signature is invalid")]
+    InvalidSignature,
+}
+
+#[cfg(feature = "http-signatures")]
+impl HttpSignatureError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::MissingComponent(_) => ErrorCode::MissingComponent,
+            Self::MissingSignature(_) => ErrorCode::MissingSignature,
+            Self::MalformedSignature => ErrorCode::MalformedSignature,
+            Self::UnsupportedAlgorithm => ErrorCode::UnsupportedAlgorithm,
+            Self::InvalidSignature => ErrorCode::InvalidSignature,
+        }
+    }
+}
+
+/// Describe error for signing and verifying JSON objects with [`crate::sign_json`] and
+/// [`crate::verify_json`].
+#[cfg(feature = "matrix")]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum JsonSignatureError {
+    /// Only JSON objects can be signed.
+    #[error("the JSON value is not an object")]
+    NotAnObject,
+    /// Canonical JSON only allows integers within `[-(2^53)+1, (2^53)-1]`.
+    #[error("the JSON value contains a number not allowed in canonical JSON")]
+    NonCanonicalNumber,
+    /// The object has to contain a signature by the requested entity and key.
+    /// Contains the key ID.
+    #[error("the object does not contain a signature by {0}")]
+    MissingSignature(String),
+    /// The signatures have to be a map of entities to unpadded base64 signatures.
+    #[error("the signatures are malformed")]
+    MalformedSignature,
+    /// The signature has to be made by the key over the canonical JSON.
+    #[error("the signature is invalid")]
+    InvalidSignature,
+}
+
+#[cfg(feature = "matrix")]
+impl JsonSignatureError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::NotAnObject => ErrorCode::NotAnObject,
+            Self::NonCanonicalNumber => ErrorCode::NonCanonicalNumber,
+            Self::MissingSignature(_) => ErrorCode::MissingSignature,
+            Self::MalformedSignature => ErrorCode::MalformedSignature,
+            Self::InvalidSignature => ErrorCode::InvalidSignature,
+        }
+    }
+}
+
+/// Describe error for issuing and verifying OpenSSH certificates with the node key.
+#[cfg(feature = "ssh")]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SshCertificateError {
+    /// The certificate has to be encodable, and its fields have to be valid.
+    #[error("SSH certificate error: {0}")]
+    Ssh(#[from] ssh_key::Error),
+    /// The certificate has to be signed by the node key, and be valid at the given time.
+    #[error("the certificate is not valid or not signed by the node key")]
+    InvalidCertificate,
+    /// The certificate has to be of the expected type.
+    #[error("the certificate has the wrong type")]
+    WrongCertificateType,
+    /// The certificate has to be valid for the principal.
+    /// Contains the principal.
+    #[error("the certificate is not valid for {0}")]
+    WrongPrincipal(String),
+}
+
+#[cfg(feature = "ssh")]
+impl SshCertificateError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Ssh(_) => ErrorCode::Ssh,
+            Self::InvalidCertificate => ErrorCode::InvalidCertificate,
+            Self::WrongCertificateType => ErrorCode::WrongCertificateType,
+            Self::WrongPrincipal(_) => ErrorCode::WrongPrincipal,
+        }
+    }
+}
+
+/// Describe error for parsing Tor v3 onion addresses with [`crate::public_key_from_onion`].
+#[cfg(feature = "tor")]
+#[derive(Error, Debug, Clone)]
+pub enum OnionError {
+    /// The address has to consist of 56 base32 characters, optionally followed by `.onion`.
+    #[error("the onion address is malformed")]
+    Malformed,
+    /// Only v3 onion addresses are supported.
+    /// Contains the version found in the address.
+    #[error("onion address version {0} is not supported")]
+    UnsupportedVersion(u8),
+    /// The checksum in the address has to match the public key.
+    #[error("the onion address checksum is wrong")]
+    WrongChecksum,
+    /// The public key in the address has to be a valid ed25519 key.
+    #[error("the onion address contains an invalid key: {0}")]
+    InvalidKey(#[source] Arc<ed25519_dalek::SignatureError>),
+}
+
+#[cfg(feature = "tor")]
+impl OnionError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Malformed => ErrorCode::MalformedOnionAddress,
+            Self::UnsupportedVersion(_) => ErrorCode::UnsupportedOnionVersion,
+            Self::WrongChecksum => ErrorCode::WrongOnionChecksum,
+            Self::InvalidKey(_) => ErrorCode::InvalidOnionKey,
+        }
+    }
+}
+
+#[cfg(feature = "tor")]
+impl From<ed25519_dalek::SignatureError> for OnionError {
+    fn from(error: ed25519_dalek::SignatureError) -> Self {
+        Self::InvalidKey(Arc::new(error))
+    }
+}
+
+#[cfg(feature = "tor")]
+impl PartialEq for OnionError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Malformed, Self::Malformed) => true,
+            (Self::UnsupportedVersion(a), Self::UnsupportedVersion(b)) => a == b,
+            (Self::WrongChecksum, Self::WrongChecksum) => true,
+            (Self::InvalidKey(a), Self::InvalidKey(b)) => eq_by_message(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// Describe error for parsing cjdns keys with [`crate::parse_cjdns_key`].
+#[cfg(feature = "cjdns")]
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CjdnsError {
+    /// The key has to consist of 52 base32 characters, optionally followed by `.k`.
+    #[error("the cjdns key is malformed")]
+    MalformedKey,
+}
+
+#[cfg(feature = "cjdns")]
+impl CjdnsError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::MalformedKey => ErrorCode::MalformedCjdnsKey,
+        }
+    }
+}
+
+/// Describe error for parsing untrusted input with [`crate::parse_any`].
+#[cfg(feature = "hex")]
+#[derive(Error, Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// The input can't be longer than [`crate::MAX_PARSE_LEN`].
+    /// Contains the length of the input.
+    #[error("input is too long: {0} bytes")]
+    TooLong(usize),
+    /// The input has to be in one of the supported encodings, and the encoding has to be enabled.
+    #[error("the input is not in a supported key format")]
+    UnknownFormat,
+    /// Keypairs have to belong together, and public keys have to be valid.
+    #[error("the input does not contain a valid key")]
+    InvalidKey,
+    /// Encrypted keys need a password, so they can't be parsed here.
+    #[error("the key is encrypted")]
+    Encrypted,
+    /// PKCS#8 and SPKI keys have to be well-formed ed25519 keys.
+    #[error("the PEM or DER encoded key is malformed")]
+    MalformedPem,
+    /// Secret keys must not look like placeholders or the output of a broken RNG,
+    /// see [`crate::NodeIdentity::is_degenerate`].
+    #[error("the secret key is degenerate")]
+    DegenerateSecret,
+    /// Key files have to be valid.
+    #[cfg(feature = "std")]
+    #[error("invalid key file: {0}")]
+    KeyFile(#[from] KeyFileError),
+}
+
+#[cfg(feature = "hex")]
+impl ParseError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::TooLong(_) => ErrorCode::InputTooLong,
+            Self::UnknownFormat => ErrorCode::UnknownKeyFormat,
+            Self::InvalidKey => ErrorCode::InvalidKey,
+            Self::Encrypted => ErrorCode::EncryptedKey,
+            Self::MalformedPem => ErrorCode::MalformedPem,
+            Self::DegenerateSecret => ErrorCode::DegenerateSecret,
+            #[cfg(feature = "std")]
+            Self::KeyFile(error) => error.code(),
+        }
+    }
+}
+
+/// Describe error for extracting public keys from X.509 certificates with
+/// [`crate::public_key_from_certificate_der`].
+#[cfg(feature = "x509")]
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertificateError {
+    /// The input has to be a DER or PEM encoded X.509 certificate.
+    #[error("the certificate is malformed")]
+    Malformed,
+    /// The certificate has to contain an ed25519 public key.
+    #[error("the certificate does not contain an ed25519 key")]
+    NotEd25519,
+    /// The ed25519 public key has to be valid.
+    #[error("the certificate contains an invalid ed25519 key")]
+    InvalidKey,
+    /// The certificate has to be signed by its own key,
+    /// unless [`crate::CertificateCheck::KeyOnly`] is used.
+    #[error("the certificate is not signed by its own key")]
+    InvalidSelfSignature,
+}
+
+#[cfg(feature = "x509")]
+impl CertificateError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Malformed => ErrorCode::MalformedCertificate,
+            Self::NotEd25519 => ErrorCode::NotEd25519Certificate,
+            Self::InvalidKey => ErrorCode::InvalidCertificateKey,
+            Self::InvalidSelfSignature => ErrorCode::InvalidCertificateSignature,
+        }
+    }
+}
+
+/// Describe error for managing key archives with [`crate::KeyArchive`].
+#[cfg(all(feature = "std", feature = "hex"))]
+#[derive(Error, Debug, Clone)]
+#[non_exhaustive]
+pub enum ArchiveError {
+    /// The archive has to be readable and writable.
+    #[error("key archive could not be accessed: {0}")]
+    Io(#[source] Arc<std::io::Error>),
+    /// The archive has to follow the format exactly.
+    /// Contains a description of the problem.
+    #[error("key archive is malformed: {0}")]
+    Malformed(&'static str),
+    /// Only known versions of the format can be read.
+    /// Contains the version of the archive.
+    #[error("key archive version {0} is not supported")]
+    UnsupportedVersion(u32),
+    /// The keys in the archive have to be valid, and belong together.
+    #[error("key archive contains invalid keys: {0}")]
+    InvalidKey(#[source] FromHexError),
+    /// The metadata of every entry has to be signed by its key.
+    #[error("the signature of an entry is invalid: {0}")]
+    InvalidSelfSignature(#[source] Arc<ed25519_dalek::SignatureError>),
+    /// Labels have to be non-empty and fit on a single line.
+    #[error("labels must not be empty or contain line breaks")]
+    InvalidLabel,
+    /// Comments have to fit on a single line.
+    #[error("comments must not contain line breaks")]
+    InvalidComment,
+    /// Labels have to be unique within an archive.
+    /// Contains the label.
+    #[error("the archive already contains an entry labelled {0:?}")]
+    DuplicateLabel(String),
+    /// Only entries in the archive can be extracted.
+    /// Contains the label.
+    #[error("the archive contains no entry labelled {0:?}")]
+    UnknownLabel(String),
+    /// Encrypted entries can only be extracted with a password.
+    #[error("the entry is encrypted")]
+    Encrypted,
+    /// Encrypted entries have to be decryptable with the supplied password.
+    #[cfg(feature = "pkcs8")]
+    #[error("the entry could not be encrypted or decrypted: {0}")]
+    Decryption(#[from] pkcs8::Error),
+}
+
+#[cfg(all(feature = "std", feature = "hex"))]
+impl ArchiveError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Io(_) => ErrorCode::Io,
+            Self::Malformed(_) => ErrorCode::MalformedArchive,
+            Self::UnsupportedVersion(_) => ErrorCode::UnsupportedArchiveVersion,
+            Self::InvalidKey(_) => ErrorCode::InvalidArchiveKey,
+            Self::InvalidSelfSignature(_) => ErrorCode::InvalidSelfSignature,
+            Self::InvalidLabel => ErrorCode::InvalidLabel,
+            Self::InvalidComment => ErrorCode::InvalidComment,
+            Self::DuplicateLabel(_) => ErrorCode::DuplicateLabel,
+            Self::UnknownLabel(_) => ErrorCode::UnknownLabel,
+            Self::Encrypted => ErrorCode::EncryptedKey,
+            #[cfg(feature = "pkcs8")]
+            Self::Decryption(_) => ErrorCode::ArchiveDecryption,
+        }
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hex"))]
+impl From<std::io::Error> for ArchiveError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(Arc::new(error))
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hex"))]
+impl From<FromHexError> for ArchiveError {
+    fn from(error: FromHexError) -> Self {
+        Self::InvalidKey(error)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hex"))]
+impl From<ed25519_dalek::SignatureError> for ArchiveError {
+    fn from(error: ed25519_dalek::SignatureError) -> Self {
+        Self::InvalidSelfSignature(Arc::new(error))
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hex"))]
+impl PartialEq for ArchiveError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Io(a), Self::Io(b)) => eq_by_message(a, b),
+            (Self::Malformed(a), Self::Malformed(b)) => a == b,
+            (Self::UnsupportedVersion(a), Self::UnsupportedVersion(b)) => a == b,
+            (Self::InvalidKey(a), Self::InvalidKey(b)) => a == b,
+            (Self::InvalidSelfSignature(a), Self::InvalidSelfSignature(b)) => eq_by_message(a, b),
+            (Self::InvalidLabel, Self::InvalidLabel) => true,
+            (Self::InvalidComment, Self::InvalidComment) => true,
+            (Self::DuplicateLabel(a), Self::DuplicateLabel(b)) => a == b,
+            (Self::UnknownLabel(a), Self::UnknownLabel(b)) => a == b,
+            (Self::Encrypted, Self::Encrypted) => true,
+            #[cfg(feature = "pkcs8")]
+            (Self::Decryption(a), Self::Decryption(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Describe error for migrating keys between config conventions with [`crate::migrate`].
+#[cfg(all(feature = "pkcs8", feature = "hex"))]
+#[derive(Error, Debug, Clone)]
+pub enum MigrationError {
+    /// The config and key files have to be readable and writable.
+    #[error("file could not be accessed: {0}")]
+    Io(#[source] Arc<std::io::Error>),
+    /// The config has to contain the setting being migrated.
+    /// Contains the name of the setting.
+    #[error("the config does not contain {0}")]
+    MissingSetting(&'static str),
+    /// Settings may only appear once in the config.
+    /// Contains the name of the setting.
+    #[error("the config contains {0} more than once")]
+    DuplicateSetting(&'static str),
+    /// The config must not set both `PrivateKey` and `PrivateKeyPath`.
+    #[error("the config contains both PrivateKey and PrivateKeyPath")]
+    ConflictingSettings,
+    /// The `PrivateKey` in the config has to be a valid hex encoded key.
+    #[error("the PrivateKey in the config is invalid: {0}")]
+    InvalidKey(#[source] FromHexError),
+    /// The key file has to contain a valid PKCS#8 encoded ed25519 key.
+    #[error("the key file is invalid: {0}")]
+    InvalidPkcs8Key(#[from] pkcs8::Error),
+    /// Key paths have to be valid unicode to be referenced from configs.
+    #[error("the key path is not valid unicode")]
+    NonUnicodePath,
+}
+
+#[cfg(all(feature = "pkcs8", feature = "hex"))]
+impl MigrationError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Io(_) => ErrorCode::Io,
+            Self::MissingSetting(_) => ErrorCode::MissingSetting,
+            Self::DuplicateSetting(_) => ErrorCode::DuplicateSetting,
+            Self::ConflictingSettings => ErrorCode::ConflictingSettings,
+            Self::InvalidKey(_) => ErrorCode::InvalidConfigKey,
+            Self::InvalidPkcs8Key(_) => ErrorCode::InvalidPkcs8Key,
+            Self::NonUnicodePath => ErrorCode::NonUnicodePath,
+        }
+    }
+}
+
+#[cfg(all(feature = "pkcs8", feature = "hex"))]
+impl From<std::io::Error> for MigrationError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(Arc::new(error))
+    }
+}
+
+#[cfg(all(feature = "pkcs8", feature = "hex"))]
+impl From<FromHexError> for MigrationError {
+    fn from(error: FromHexError) -> Self {
+        Self::InvalidKey(error)
+    }
+}
+
+#[cfg(all(feature = "pkcs8", feature = "hex"))]
+impl PartialEq for MigrationError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Io(a), Self::Io(b)) => eq_by_message(a, b),
+            (Self::MissingSetting(a), Self::MissingSetting(b)) => a == b,
+            (Self::DuplicateSetting(a), Self::DuplicateSetting(b)) => a == b,
+            (Self::ConflictingSettings, Self::ConflictingSettings) => true,
+            (Self::InvalidKey(a), Self::InvalidKey(b)) => a == b,
+            (Self::InvalidPkcs8Key(a), Self::InvalidPkcs8Key(b)) => a == b,
+            (Self::NonUnicodePath, Self::NonUnicodePath) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Describe error for deriving identities with [`crate::NodeIdentity::from_passphrase`].
+#[cfg(feature = "passphrase")]
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassphraseError {
+    /// The passphrase must not be empty.
+    #[error("the passphrase is empty")]
+    EmptyPassphrase,
+    /// The salt and cost parameters have to be accepted by Argon2.
+    #[error("invalid Argon2 parameters: {0}")]
+    Argon2(argon2::Error),
+}
+
+#[cfg(feature = "passphrase")]
+impl From<argon2::Error> for PassphraseError {
+    fn from(error: argon2::Error) -> Self {
+        Self::Argon2(error)
+    }
+}
+
+#[cfg(feature = "passphrase")]
+impl PassphraseError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::EmptyPassphrase => ErrorCode::EmptyPassphrase,
+            Self::Argon2(_) => ErrorCode::InvalidPassphraseParams,
+        }
+    }
+}
+
+/// Describe error for reading and writing TOML config fragments with
+/// [`crate::NodeIdentity::from_toml`] and [`crate::PeerDb::from_toml`].
+#[cfg(feature = "toml")]
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum TomlError {
+    /// The values have to be representable in TOML.
+    #[error("could not serialize to TOML: {0}")]
+    Serialization(#[from] toml::ser::Error),
+    /// The input has to be valid TOML with the expected tables.
+    #[error("TOML fragment is malformed: {0}")]
+    Malformed(#[from] toml::de::Error),
+    /// The keys in the fragment have to be valid, and belong together.
+    #[error("TOML fragment contains invalid keys: {0}")]
+    InvalidKey(#[source] FromHexError),
+    /// The address and subnet, if present, have to be derived from the public key.
+    #[error("the address does not belong to the public key")]
+    WrongAddress,
+}
+
+#[cfg(feature = "toml")]
+impl TomlError {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Serialization(_) => ErrorCode::TomlSerialization,
+            Self::Malformed(_) => ErrorCode::MalformedToml,
+            Self::InvalidKey(_) => ErrorCode::InvalidTomlKey,
+            Self::WrongAddress => ErrorCode::WrongTomlAddress,
+        }
+    }
+}
+
+/// Describe error for reading node identities from PKCS#12 bundles.
+#[cfg(feature = "pkcs12")]
+#[derive(Error, Debug, Clone)]
+pub enum Pkcs12Error {
+    /// The bundle has to be valid PKCS#12, encrypted with the supplied password.
+    #[error("the PKCS#12 bundle could not be read: {0}")]
+    Pkcs12(#[source] Arc<p12_keystore::error::Error>),
+    /// The bundle has to contain an ed25519 private key.
+    #[error("the PKCS#12 bundle does not contain an ed25519 private key")]
+    NoEd25519Key,
+    /// The ed25519 private key has to be valid PKCS#8.
+    #[error("the private key is invalid: {0}")]
+    InvalidKey(#[source] ed25519_dalek::pkcs8::Error),
+}
+
+#[cfg(feature = "pkcs12")]
+impl Pkcs12Error {
+    /// The stable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Pkcs12(_) => ErrorCode::InvalidPkcs12,
+            Self::NoEd25519Key => ErrorCode::NoEd25519Key,
+            Self::InvalidKey(_) => ErrorCode::InvalidPkcs8Key,
+        }
+    }
+}
+
+#[cfg(feature = "pkcs12")]
+impl From<p12_keystore::error::Error> for Pkcs12Error {
+    fn from(error: p12_keystore::error::Error) -> Self {
+        Self::Pkcs12(Arc::new(error))
+    }
+}
+
+#[cfg(feature = "pkcs12")]
+impl PartialEq for Pkcs12Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Pkcs12(a), Self::Pkcs12(b)) => eq_by_message(a, b),
+            (Self::NoEd25519Key, Self::NoEd25519Key) => true,
+            (Self::InvalidKey(a), Self::InvalidKey(b)) => a == b,
+            _ => false,
+        }
+    }
 }
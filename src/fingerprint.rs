@@ -0,0 +1,204 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use core::fmt;
+use core::str::FromStr;
+
+use base64ct::{Base64Unpadded, Encoding};
+use ed25519_dalek::VerifyingKey;
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::{FingerprintError, NodeIdentity, PublicNodeIdentity};
+
+/// Domain separation prefix for the hashes fingerprints are taken from
+const FINGERPRINT_CONTEXT: &[u8] = b"yggdrasil-keys emoji fingerprint v1";
+
+/// The algorithm prefix of [`Fingerprint`]s
+const SHA256_PREFIX: &str = "SHA256:";
+
+/// The OpenSSH key type of ed25519 keys
+const SSH_ED25519: &[u8] = b"ssh-ed25519";
+
+/// The number of emoji in a fingerprint, see [`emoji_fingerprint`]
+pub const EMOJI_FINGERPRINT_LEN: usize = 10;
+
+/// An emoji in a fingerprint, together with its name for comparing fingerprints over voice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Emoji {
+    /// The emoji itself
+    pub symbol: &'static str,
+    /// The English name of the emoji
+    pub name: &'static str,
+}
+
+impl fmt::Display for Emoji {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol)
+    }
+}
+
+macro_rules! emoji {
+    ($($symbol:literal $name:literal),* $(,)?) => {
+        [$(Emoji { symbol: $symbol, name: $name }),*]
+    };
+}
+
+/// The emoji used for short authentication strings in Matrix, chosen to be easy to tell apart
+/// and to name.
+const EMOJI: [Emoji; 64] = emoji![
+    "🐶" "Dog", "🐱" "Cat", "🦁" "Lion", "🐎" "Horse",
+    "🦄" "Unicorn", "🐷" "Pig", "🐘" "Elephant", "🐰" "Rabbit",
+    "🐼" "Panda", "🐓" "Rooster", "🐧" "Penguin", "🐢" "Turtle",
+    "🐟" "Fish", "🐙" "Octopus", "🦋" "Butterfly", "🌷" "Flower",
+    "🌳" "Tree", "🌵" "Cactus", "🍄" "Mushroom", "🌏" "Globe",
+    "🌙" "Moon", "☁️" "Cloud", "🔥" "Fire", "🍌" "Banana",
+    "🍎" "Apple", "🍓" "Strawberry", "🌽" "Corn", "🍕" "Pizza",
+    "🎂" "Cake", "❤️" "Heart", "😀" "Smiley", "🤖" "Robot",
+    "🎩" "Hat", "👓" "Glasses", "🔧" "Spanner", "🎅" "Santa",
+    "👍" "Thumbs Up", "☂️" "Umbrella", "⌛" "Hourglass", "⏰" "Clock",
+    "🎁" "Gift", "💡" "Light Bulb", "📕" "Book", "✏️" "Pencil",
+    "📎" "Paperclip", "✂️" "Scissors", "🔒" "Lock", "🔑" "Key",
+    "🔨" "Hammer", "☎️" "Telephone", "🏁" "Flag", "🚂" "Train",
+    "🚲" "Bicycle", "✈️" "Aeroplane", "🚀" "Rocket", "🏆" "Trophy",
+    "⚽" "Ball", "🎸" "Guitar", "🎺" "Trumpet", "🔔" "Bell",
+    "⚓" "Anchor", "🎧" "Headphones", "📁" "Folder", "📌" "Pin",
+];
+
+/// Render a public key as a sequence of emoji, for comparing keys by voice or in chats.
+///
+/// The fingerprint encodes the first 60 bits of the SHA-512 hash of the context
+/// `yggdrasil-keys emoji fingerprint v1` and the public key, six bits per emoji, using the emoji
+/// Matrix uses for verifying devices. Each emoji has a name, so fingerprints can be read out
+/// loud as well. Finding a key with a given fingerprint takes around 2^60 attempts, so matching
+/// fingerprints are good evidence of talking about the same key, but fingerprints are no
+/// replacement for comparing full keys in automated checks.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{emoji_fingerprint, NodeIdentity};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let fingerprint = node.emoji_fingerprint();
+/// assert_eq!(fingerprint, emoji_fingerprint(&node.public_key()));
+/// let names: Vec<_> = fingerprint.iter().map(|emoji| emoji.name).collect();
+/// println!("{}", names.join(", "));
+/// ```
+pub fn emoji_fingerprint(public_key: &VerifyingKey) -> [Emoji; EMOJI_FINGERPRINT_LEN] {
+    let hash = Sha512::new()
+        .chain_update(FINGERPRINT_CONTEXT)
+        .chain_update(public_key.as_bytes())
+        .finalize();
+    let mut bits = [0u8; 8];
+    bits.copy_from_slice(&hash[0..8]);
+    let bits = u64::from_be_bytes(bits);
+    let mut fingerprint = [EMOJI[0]; EMOJI_FINGERPRINT_LEN];
+    for (index, emoji) in fingerprint.iter_mut().enumerate() {
+        *emoji = EMOJI[((bits >> (58 - 6 * index)) & 0x3f) as usize];
+    }
+    fingerprint
+}
+
+impl NodeIdentity {
+    /// Render the node key as a sequence of emoji, see [`emoji_fingerprint`].
+    pub fn emoji_fingerprint(&self) -> [Emoji; EMOJI_FINGERPRINT_LEN] {
+        emoji_fingerprint(&self.signing_keys.verifying_key())
+    }
+}
+
+/// An OpenSSH style SHA256 fingerprint of a public key, for reading keys out over the phone.
+///
+/// The fingerprint is the SHA256 hash of the key in the OpenSSH wire format, so it is the same one
+/// `ssh-keygen -l` shows for an `ssh-ed25519` key with the same bytes. Fingerprints are displayed
+/// and parsed as `SHA256:` followed by the unpadded base64 of the hash.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{Fingerprint, NodeIdentity};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let read_out = node.fingerprint().to_string();
+/// assert!(read_out.starts_with("SHA256:"));
+/// let fingerprint: Fingerprint = read_out.parse().unwrap();
+/// assert!(fingerprint.matches(&node.public_key()));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Fingerprint([u8; 32]);
+
+impl Fingerprint {
+    /// Calculate the fingerprint of a public key.
+    pub fn new(public_key: &VerifyingKey) -> Self {
+        let hash = Sha256::new()
+            .chain_update((SSH_ED25519.len() as u32).to_be_bytes())
+            .chain_update(SSH_ED25519)
+            .chain_update(32u32.to_be_bytes())
+            .chain_update(public_key.as_bytes())
+            .finalize();
+        Self(hash.into())
+    }
+
+    /// Whether this is the fingerprint of the given public key.
+    pub fn matches(&self, public_key: &VerifyingKey) -> bool {
+        *self == Self::new(public_key)
+    }
+
+    /// The SHA256 hash
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+/// Parses fingerprints starting with `SHA256:`, as shown by [`Fingerprint`]'s `Display`.
+impl FromStr for Fingerprint {
+    type Err = FingerprintError;
+
+    fn from_str(fingerprint: &str) -> Result<Self, FingerprintError> {
+        let encoded = fingerprint
+            .strip_prefix(SHA256_PREFIX)
+            .ok_or(FingerprintError::UnsupportedAlgorithm)?;
+        let mut hash = [0u8; 32];
+        let decoded = Base64Unpadded::decode(encoded, &mut hash)
+            .map_err(|_| FingerprintError::InvalidEncoding)?;
+        if decoded.len() != hash.len() {
+            return Err(FingerprintError::InvalidEncoding);
+        }
+        Ok(Self(hash))
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buffer = [0u8; 43];
+        let encoded = Base64Unpadded::encode(&self.0, &mut buffer).map_err(|_| fmt::Error)?;
+        write!(f, "{}{}", SHA256_PREFIX, encoded)
+    }
+}
+
+impl NodeIdentity {
+    /// The OpenSSH style SHA256 fingerprint of the node key, see [`Fingerprint`].
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint::new(&self.signing_keys.verifying_key())
+    }
+}
+
+impl PublicNodeIdentity {
+    /// The OpenSSH style SHA256 fingerprint of the node key, see [`Fingerprint`].
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint::new(&self.public_key())
+    }
+}
@@ -0,0 +1,103 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use std::net::Ipv6Addr;
+
+use crate::{helper::address_bytes, NodeIdentity};
+
+/// Which networks of each node to put into packet filter sets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FilterEntries {
+    /// Only the node addresses, as `/128`s
+    Addresses,
+    /// Only the routed `/64` subnets of the nodes
+    Subnets,
+    /// Both the addresses and the subnets of the nodes
+    Both,
+}
+
+/// Format the networks of a list of nodes as an nftables set element list, like `{ a, b }`.
+///
+/// The result can be used in `add element` commands as well as in `elements = ...` set
+/// definitions. Sets containing subnets need the `interval` flag. nftables rejects empty element
+/// lists, so `None` is returned if there are no nodes.
+///
+/// Nodes can be given as raw public keys as well as [`NodeIdentity`]s and
+/// [`PublicNodeIdentity`](crate::PublicNodeIdentity)s.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{nft_elements, FilterEntries, NodeIdentity};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let elements = nft_elements(vec![&node], FilterEntries::Addresses).unwrap();
+/// let command = format!("add element inet filter mesh_peers {}", elements);
+/// assert_eq!(command, format!("add element inet filter mesh_peers {{ {} }}", node.address()));
+/// ```
+pub fn nft_elements<I>(nodes: I, entries: FilterEntries) -> Option<String>
+where
+    I: IntoIterator,
+    I::Item: Into<[u8; 32]>,
+{
+    let elements = filter_entries(nodes, entries);
+    if elements.is_empty() {
+        return None;
+    }
+    Some(format!("{{ {} }}", elements.join(", ")))
+}
+
+/// Format the networks of a list of nodes as `ipset restore` input, adding them to the given set.
+///
+/// The set has to be of type `hash:net` with `family inet6`, which accepts both addresses and
+/// subnets. Like with [`nft_elements`], nodes can be given as raw public keys or identities.
+pub fn ipset_restore<I>(set: &str, nodes: I, entries: FilterEntries) -> String
+where
+    I: IntoIterator,
+    I::Item: Into<[u8; 32]>,
+{
+    filter_entries(nodes, entries)
+        .iter()
+        .map(|entry| format!("add {} {}\n", set, entry))
+        .collect()
+}
+
+fn filter_entries<I>(nodes: I, entries: FilterEntries) -> Vec<String>
+where
+    I: IntoIterator,
+    I::Item: Into<[u8; 32]>,
+{
+    let (addresses, subnets) = match entries {
+        FilterEntries::Addresses => (true, false),
+        FilterEntries::Subnets => (false, true),
+        FilterEntries::Both => (true, true),
+    };
+    let mut result = Vec::new();
+    for node in nodes {
+        let public_key = node.into();
+        if addresses {
+            let address = address_bytes(&public_key, &NodeIdentity::IP_PREFIX, false);
+            result.push(Ipv6Addr::from(address).to_string());
+        }
+        if subnets {
+            let subnet = address_bytes(&public_key, &NodeIdentity::IP_PREFIX, true);
+            result.push(format!("{}/64", Ipv6Addr::from(subnet)));
+        }
+    }
+    result
+}
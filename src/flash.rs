@@ -0,0 +1,138 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use zeroize::Zeroizing;
+
+use crate::{
+    audit::{audit, KeyOperation},
+    FlashError, NodeIdentity,
+};
+
+/// Magic bytes at the start of every flash image
+pub const FLASH_MAGIC: [u8; 4] = *b"YGGK";
+
+/// The version of the flash layout written by this crate
+pub const FLASH_VERSION: u8 = 1;
+
+/// The size of the largest flash image written by this crate
+pub const FLASH_MAX_LEN: usize = HEADER_LEN + 64;
+
+/// Flag signalling that the public key follows the secret key
+const FLAG_PUBLIC_KEY: u8 = 0x01;
+
+/// Flags that decoders have to understand, unknown ones cause the image to be rejected
+const REQUIRED_FLAGS: u8 = 0x0f;
+
+const HEADER_LEN: usize = FLASH_MAGIC.len() + 2;
+
+impl NodeIdentity {
+    /// Write this identity into a buffer, in a compact binary layout for flash or EEPROM.
+    ///
+    /// The layout is:
+    ///  - 4 bytes [`FLASH_MAGIC`]
+    ///  - 1 byte version, currently [`FLASH_VERSION`]
+    ///  - 1 byte flags
+    ///  - 32 bytes secret key
+    ///  - 32 bytes public key, if the lowest flag bit is set
+    ///
+    /// The version only changes for incompatible changes to the layout. Compatible additions are
+    /// appended after the known fields, and signalled with one of the upper four flag bits, which
+    /// decoders ignore if they don't know them. The lower four flag bits mark additions that
+    /// decoders have to understand, so images with unknown lower flags are rejected.
+    ///
+    /// Storing the public key costs 32 bytes, but allows checking the image for corruption.
+    /// Returns the number of bytes written. The buffer contains the secret key afterwards,
+    /// so zeroize it when you're done with it.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::{NodeIdentity, FLASH_MAX_LEN};
+    ///
+    /// let node = NodeIdentity::new(&mut thread_rng());
+    /// let mut flash = [0xffu8; 128];
+    /// assert_eq!(node.to_flash(&mut flash, true).unwrap(), FLASH_MAX_LEN);
+    /// let restored = NodeIdentity::from_flash(&flash).unwrap();
+    /// assert_eq!(restored.address(), node.address());
+    /// ```
+    pub fn to_flash(
+        &self,
+        buffer: &mut [u8],
+        include_public_key: bool,
+    ) -> Result<usize, FlashError> {
+        let len = if include_public_key {
+            FLASH_MAX_LEN
+        } else {
+            HEADER_LEN + 32
+        };
+        if buffer.len() < len {
+            return Err(FlashError::BufferTooSmall(len));
+        }
+        buffer[0..4].copy_from_slice(&FLASH_MAGIC);
+        buffer[4] = FLASH_VERSION;
+        buffer[5] = if include_public_key {
+            FLAG_PUBLIC_KEY
+        } else {
+            0
+        };
+        audit(self, KeyOperation::Export, "NodeIdentity::to_flash");
+        buffer[HEADER_LEN..HEADER_LEN + 32].copy_from_slice(self.signing_keys.as_bytes());
+        if include_public_key {
+            buffer[HEADER_LEN + 32..len]
+                .copy_from_slice(self.signing_keys.verifying_key().as_bytes());
+        }
+        Ok(len)
+    }
+
+    /// Read an identity from a buffer written by [`NodeIdentity::to_flash`].
+    ///
+    /// Trailing bytes after the image are ignored, so the whole flash page can be passed.
+    pub fn from_flash(buffer: &[u8]) -> Result<Self, FlashError> {
+        if buffer.len() < HEADER_LEN {
+            return Err(FlashError::Truncated);
+        }
+        if buffer[0..4] != FLASH_MAGIC {
+            return Err(FlashError::BadMagic);
+        }
+        if buffer[4] != FLASH_VERSION {
+            return Err(FlashError::UnsupportedVersion(buffer[4]));
+        }
+        let flags = buffer[5];
+        if flags & REQUIRED_FLAGS & !FLAG_PUBLIC_KEY != 0 {
+            return Err(FlashError::UnknownFlags(flags));
+        }
+        let len = if flags & FLAG_PUBLIC_KEY != 0 {
+            FLASH_MAX_LEN
+        } else {
+            HEADER_LEN + 32
+        };
+        if buffer.len() < len {
+            return Err(FlashError::Truncated);
+        }
+
+        let mut secret = Zeroizing::new([0u8; 32]);
+        secret.copy_from_slice(&buffer[HEADER_LEN..HEADER_LEN + 32]);
+        let signing_keys = ed25519_dalek::SigningKey::from_bytes(&secret);
+        if flags & FLAG_PUBLIC_KEY != 0
+            && signing_keys.verifying_key().as_bytes()[..] != buffer[HEADER_LEN + 32..len]
+        {
+            return Err(FlashError::WrongPublicKey);
+        }
+        Ok(Self { signing_keys })
+    }
+}
@@ -0,0 +1,128 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use hkdf::Hkdf;
+use sha2::Sha512;
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+use crate::{
+    audit::{audit, KeyOperation},
+    helper::{create_secret_file, normalize_hex},
+    NodeIdentity,
+};
+
+/// HKDF info string prefix used to derive fleet node keys from a master seed
+const FLEET_INFO: &[u8] = b"yggdrasil-keys v1 fleet node ";
+
+/// Name of the inventory manifest written by [`write_fleet`]
+pub const INVENTORY_FILE: &str = "inventory.tsv";
+
+/// Derive the node identity with the given index from a master seed.
+///
+/// The key is derived with HKDF-SHA512, using the index as part of the info string,
+/// so every index results in an unrelated key, and the same seed and index always result in the
+/// same key.
+pub fn derive_fleet_node(seed: &[u8; 32], index: u64) -> NodeIdentity {
+    let hkdf = Hkdf::<Sha512>::new(None, seed);
+    let mut secret = Zeroizing::new([0u8; 32]);
+    hkdf.expand_multi_info(&[FLEET_INFO, &index.to_be_bytes()], &mut secret[..])
+        .unwrap();
+    let signing_keys = ed25519_dalek::SigningKey::from_bytes(&secret);
+    NodeIdentity { signing_keys }
+}
+
+/// Read a master seed for [`derive_fleet`] from a file containing 32 hex encoded bytes.
+///
+/// Surrounding whitespace is ignored, so the file may end with a newline.
+pub fn read_fleet_seed(path: &Path) -> io::Result<Zeroizing<[u8; 32]>> {
+    let contents = Zeroizing::new(fs::read_to_string(path)?);
+    let mut seed = Zeroizing::new([0u8; 32]);
+    hex::decode_to_slice(normalize_hex(&contents).as_str(), &mut seed[..]).map_err(|_| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            "seed file has to contain 32 hex encoded bytes",
+        )
+    })?;
+    Ok(seed)
+}
+
+/// Derive `count` node identities from a master seed, with the indices `0..count`.
+pub fn derive_fleet(seed: &[u8; 32], count: u64) -> Vec<NodeIdentity> {
+    (0..count)
+        .map(|index| derive_fleet_node(seed, index))
+        .collect()
+}
+
+/// Write key files and an inventory manifest for a fleet of nodes into a directory.
+///
+/// The nodes are given together with their index, which for derived nodes should be the index
+/// they were derived with. For every node, a file named `node-<index>.key` containing the hex
+/// encoded keypair is created. On Unix, key files are only readable by their owner. Once all key
+/// files are written, [`INVENTORY_FILE`] is created, containing a tab separated line with the
+/// name, public key, address and subnet of each node. Existing files are never overwritten.
+///
+/// ```rust,no_run
+/// use yggdrasil_keys::{derive_fleet_node, write_fleet};
+///
+/// let seed = [42u8; 32];
+/// let nodes: Vec<_> = (10..20).map(|index| derive_fleet_node(&seed, index)).collect();
+/// write_fleet((10..).zip(&nodes), "fleet".as_ref()).unwrap();
+/// ```
+pub fn write_fleet<'a, I>(nodes: I, out_dir: &Path) -> io::Result<()>
+where
+    I: IntoIterator<Item = (u64, &'a NodeIdentity)>,
+{
+    fs::create_dir_all(out_dir)?;
+    let inventory_path = out_dir.join(INVENTORY_FILE);
+    // Fail before writing any keys into a directory with an existing fleet
+    if inventory_path.exists() {
+        return Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            "fleet inventory exists already",
+        ));
+    }
+    let mut inventory = String::from("name\tpublic_key\taddress\tsubnet\n");
+    for (index, node) in nodes {
+        let name = format!("node-{}", index);
+
+        let mut file = create_secret_file(&out_dir.join(format!("{}.key", name)))?;
+        audit(node, KeyOperation::Export, "write_fleet");
+        let keypair = Zeroizing::new(node.hex_joined());
+        file.write_all(keypair.as_bytes())?;
+        file.write_all(b"\n")?;
+
+        let public = hex::encode(node.signing_keys.verifying_key().as_bytes());
+        inventory.push_str(&format!(
+            "{}\t{}\t{}\t{}\n",
+            name,
+            public,
+            node.address(),
+            node.subnet()
+        ));
+    }
+    // Written last, so an interrupted run doesn't leave an incomplete inventory behind
+    let mut inventory_file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(inventory_path)?;
+    inventory_file.write_all(inventory.as_bytes())
+}
@@ -17,46 +17,155 @@
  *   You should have received a copy of the GNU Affero General Public License   *
  *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
  ********************************************************************************/
-use std::convert::TryInto;
+#[cfg(feature = "hex")]
+use core::convert::TryInto;
 
+use sha2::{Digest, Sha512};
+#[cfg(feature = "hex")]
+use zeroize::Zeroizing;
+
+#[cfg(feature = "hex")]
 use crate::FromHexError;
 
-/// count the leading ones on a byte array
-pub(crate) fn leading_ones<const T: usize>(array: [u8; T]) -> u32 {
-    let mut leading_ones = 0u32;
-    while (leading_ones / 8) < 64 {
-        let current_byte = array[(leading_ones / 8) as usize];
-        let local_leading_ones = current_byte.leading_ones();
-        leading_ones += local_leading_ones;
-        // Break if there's a one in the byte
-        if local_leading_ones != 8 {
-            break;
-        }
+const ADDR_BYTE: u8 = 0xfeu8;
+const SNET_BYTE: u8 = 0x01u8;
+
+/// Split a public key into two inverted big endian words.
+fn inverted_words(public_key: &[u8; 32]) -> (u128, u128) {
+    let mut high = [0u8; 16];
+    let mut low = [0u8; 16];
+    high.copy_from_slice(&public_key[0..16]);
+    low.copy_from_slice(&public_key[16..32]);
+    (!u128::from_be_bytes(high), !u128::from_be_bytes(low))
+}
+
+/// Count the leading ones of two words, as if they were a single one.
+fn words_leading_ones(high: u128, low: u128) -> u32 {
+    match high {
+        u128::MAX => 128 + low.leading_ones(),
+        high => high.leading_ones(),
     }
-    leading_ones
 }
 
-/// count the leading ones on a byte array,
+/// Count the leading ones of the inverted public key.
+///
+/// Word-wise version of [`crate::bits::leading_ones`], for public keys.
+pub(crate) fn inverted_leading_ones(public_key: &[u8; 32]) -> u32 {
+    let (high, low) = inverted_words(public_key);
+    words_leading_ones(high, low)
+}
+
+/// Like [`strip_inverted_ones`], but returning the remainder as two big endian words.
+pub(crate) fn strip_inverted_words(public_key: &[u8; 32]) -> (u32, u128, u128) {
+    let (high, low) = inverted_words(public_key);
+    let ones = words_leading_ones(high, low);
+    let (high, low) = match ones + 1 {
+        strip @ 1..=127 => ((high << strip) | (low >> (128 - strip)), low << strip),
+        strip @ 128..=255 => (low << (strip - 128), 0),
+        _ => (0, 0),
+    };
+    (ones, high, low)
+}
+
+/// Count the leading ones of the inverted public key,
 /// strip them plus the following zero off,
-/// return the count and the remainder.
-pub(crate) fn strip_ones<const T: usize>(array: [u8; T]) -> (u32, Vec<u8>) {
-    let ones = leading_ones(array);
-    let strip = ones + 1;
-    let shift = (strip % 8u32) as u8;
-    // Cut away everything we'd drop anyway
-    let mut slice = Vec::from(&array[((strip / 8u32) as usize)..]);
-    if shift != 0 {
-        for i in 0..slice.len() - 1 {
-            let lhs: u8 = slice[i] << shift;
-            let rhs: u8 = slice[i + 1] >> (8u8 - shift);
-            slice[i] = lhs | rhs;
-        }
-        let slice_len = slice.len();
-        slice[slice_len - 1] <<= shift;
+/// return the count and the remainder, padded with zeroes.
+///
+/// Word-wise version of [`crate::bits::strip_ones`], for public keys.
+pub(crate) fn strip_inverted_ones(public_key: &[u8; 32]) -> (u32, [u8; 32]) {
+    let (ones, high, low) = strip_inverted_words(public_key);
+    let mut remainder = [0u8; 32];
+    remainder[0..16].copy_from_slice(&high.to_be_bytes());
+    remainder[16..32].copy_from_slice(&low.to_be_bytes());
+    (ones, remainder)
+}
+
+/// Calculate the address bytes for a public key.
+pub(crate) fn address_bytes(public_key: &[u8; 32], prefix: &[u8], net: bool) -> [u8; 16] {
+    // Prefix must be at most a /48 for subnets or /112 for addresses
+    assert!(prefix.len() <= if net { 6 } else { 14 });
+
+    // Create 16 bytes array and copy the prefix into it
+    let mut bytes: [u8; 16] = [0u8; 16];
+    bytes[0..prefix.len()].copy_from_slice(prefix);
+
+    // Set the last bit of the prefix to one if its a subnet,
+    // or zero if its an address
+    bytes[prefix.len() - 1] = if net {
+        bytes[prefix.len() - 1] | SNET_BYTE
+    } else {
+        bytes[prefix.len() - 1] & ADDR_BYTE
+    };
+
+    // Count the leading ones in the Node ID,
+    // and strip them plus the following zero.
+    let (ones, remainder) = strip_inverted_ones(public_key);
+
+    // Set the next byte to the the number of ones stripped from the Node ID
+    bytes[prefix.len()] = ones as u8;
+
+    // Set the remaining bytes until the end of the address/subnet
+    // to the remainder of the Node ID
+    let end = if net { 8 } else { 16 };
+    bytes[(prefix.len() + 1)..end].copy_from_slice(&remainder[0..(end - (prefix.len() + 1))]);
+
+    // Return the address bytes
+    bytes
+}
+
+/// Check whether a seed looks like a placeholder or the output of a broken RNG.
+///
+/// This catches seeds repeating a pattern of up to four bytes, like all zeroes or `deadbeef`,
+/// and seeds counting up or down by one, like `000102...1f`.
+pub(crate) fn is_degenerate_seed(seed: &[u8; 32]) -> bool {
+    let repeating = (1..=4).any(|period| (period..32).all(|i| seed[i] == seed[i - period]));
+    let counting = [1u8, 255].iter().any(|&step| {
+        seed.windows(2)
+            .all(|pair| pair[1] == pair[0].wrapping_add(step))
+    });
+    repeating || counting
+}
+
+/// Derive a locally administered unicast MAC address from a public key.
+pub(crate) fn mac_address(public_key: &[u8; 32]) -> [u8; 6] {
+    let hash = Sha512::digest(public_key);
+    let mut mac = [0u8; 6];
+    mac.copy_from_slice(&hash[0..6]);
+    // Set the locally administered bit, clear the multicast bit
+    mac[0] = (mac[0] | 0x02) & !0x01;
+    mac
+}
+
+/// Turn a MAC address into a modified EUI-64 interface identifier.
+pub(crate) fn eui64(mac: [u8; 6]) -> u64 {
+    let identifier = [
+        mac[0] ^ 0x02,
+        mac[1],
+        mac[2],
+        0xff,
+        0xfe,
+        mac[3],
+        mac[4],
+        mac[5],
+    ];
+    u64::from_be_bytes(identifier)
+}
+
+/// Decode a public key from 32 hex encoded bytes
+#[cfg(feature = "hex")]
+pub(crate) fn public_hex_to_bytes(public: &str) -> Result<[u8; 32], FromHexError> {
+    if public.len() != 64 {
+        return Err(FromHexError::WrongPublicKeyLength(public.len()));
     }
-    (ones, slice)
+    let mut bytes = [0u8; 32];
+    hex::decode_to_slice(public, &mut bytes)?;
+    Ok(bytes)
 }
 
+/// A secret key, plus the public key if it was supplied
+#[cfg(feature = "hex")]
+pub(crate) type SecretAndPublic = (Zeroizing<[u8; 32]>, Option<[u8; 32]>);
+
 /// Get one or two 32 byte arrays out of one or two strings
 ///
 /// You probably want to look at [`crate::NodeIdentity::from_hex`]
@@ -75,43 +184,183 @@ pub(crate) fn strip_ones<const T: usize>(array: [u8; T]) -> (u32, Vec<u8>) {
 /// one byte array of length 32 will be returned.
 ///
 /// If anything else is passed, an error will be returned.
+///
+/// The secret is only ever decoded into stack buffers, which are zeroized on drop.
+#[cfg(feature = "hex")]
 pub(crate) fn hex_pair_to_bytes(
     secret: &str,
     public: Option<&str>,
-) -> Result<([u8; 32], Option<[u8; 32]>), FromHexError> {
-    let sec_bytes = hex::decode(secret)?;
-    let (sec_bytes, pub_bytes): ([u8; 32], Option<[u8; 32]>) = match sec_bytes.len() {
-        64 => {
-            let pub_bytes = sec_bytes[32..].try_into().unwrap();
-            let sec_bytes = sec_bytes[0..32].try_into().unwrap();
-            (sec_bytes, Some(pub_bytes))
+) -> Result<SecretAndPublic, FromHexError> {
+    let length = match secret.len() {
+        128 => 64,
+        64 => 32,
+        _ => return Err(FromHexError::WrongSecretKeyLength(secret.len())),
+    };
+    let mut buffer = Zeroizing::new([0u8; 64]);
+    hex::decode_to_slice(secret, &mut buffer[0..length])?;
+    let mut sec_bytes = Zeroizing::new([0u8; 32]);
+    sec_bytes.copy_from_slice(&buffer[0..32]);
+    let pub_bytes: Option<[u8; 32]> = match length {
+        64 => Some(buffer[32..].try_into().unwrap()),
+        _ => None,
+    };
+
+    let pub_bytes: Option<[u8; 32]> = match (pub_bytes, public) {
+        (Some(pub_bytes), Some(pub_hex)) => {
+            if pub_bytes != public_hex_to_bytes(pub_hex)? {
+                return Err(FromHexError::ConflictingPubKeys);
+            }
+            Some(pub_bytes)
         }
-        32 => (sec_bytes[0..32].try_into().unwrap(), None),
-        _ => return Err(FromHexError::WrongKeyLength),
+        (Some(pub_bytes), None) => Some(pub_bytes),
+        (None, Some(pub_hex)) => Some(public_hex_to_bytes(pub_hex)?),
+        (None, None) => None,
     };
+    Ok((sec_bytes, pub_bytes))
+}
+
+/// Normalize hex copied from elsewhere: strip whitespace and a `0x` prefix, and lowercase it.
+#[cfg(all(feature = "std", feature = "hex"))]
+pub(crate) fn normalize_hex(hex: &str) -> Zeroizing<String> {
+    let trimmed = hex.trim();
+    let unprefixed = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed);
+    let mut normalized = Zeroizing::new(String::with_capacity(unprefixed.len()));
+    normalized.extend(
+        unprefixed
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| c.to_ascii_lowercase()),
+    );
+    normalized
+}
+
+/// Create a new file that is only readable by its owner on Unix.
+/// Fails if the file exists already.
+#[cfg(all(feature = "std", feature = "hex"))]
+pub(crate) fn create_secret_file(path: &std::path::Path) -> std::io::Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+/// Replace a file by writing next to it first and moving it into place, keeping it only
+/// readable by its owner on Unix.
+///
+/// A temporary file left behind by an interrupted earlier write is removed first.
+#[cfg(all(feature = "std", feature = "hex"))]
+pub(crate) fn replace_secret_file(path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+    replace_file_with(path, contents, create_secret_file)
+}
+
+/// Replace a file like [`replace_secret_file`], but with the default permissions.
+#[cfg(feature = "serde")]
+pub(crate) fn replace_file(path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+    replace_file_with(path, contents, |path| {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+    })
+}
+
+/// Write the contents to a temporary file created with `create`, sync it and move it into place.
+#[cfg(all(feature = "std", feature = "hex"))]
+fn replace_file_with(
+    path: &std::path::Path,
+    contents: &[u8],
+    create: impl FnOnce(&std::path::Path) -> std::io::Result<std::fs::File>,
+) -> std::io::Result<()> {
+    use std::io::{ErrorKind, Write};
+
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = std::path::PathBuf::from(temporary);
+    match std::fs::remove_file(&temporary) {
+        Err(error) if error.kind() != ErrorKind::NotFound => return Err(error),
+        _ => {}
+    }
+    let mut file = create(&temporary)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    std::fs::rename(&temporary, path)
+}
 
-    let pub_bytes: Option<[u8; 32]> = match pub_bytes {
-        Some(pub_bytes) => match public {
-            Some(pub_hex) => {
-                let pub_bytes_from_option = &hex::decode(pub_hex)?[0..32];
-                if pub_bytes != pub_bytes_from_option {
-                    return Err(FromHexError::ConflictingPubKeys);
-                } else {
-                    Some(pub_bytes)
+/// Read the next line of a text format with `Name: value` lines, if it is the given field.
+#[cfg(all(feature = "std", feature = "hex"))]
+pub(crate) fn field<'a>(lines: &mut core::str::Lines<'a>, name: &str) -> Option<&'a str> {
+    lines
+        .next()
+        .and_then(|line| line.strip_prefix(name))
+        .and_then(|line| line.strip_prefix(": "))
+}
+
+/// Serialize byte arrays as hex strings, for use with `#[serde(with = "...")]`
+#[cfg(feature = "serde")]
+pub(crate) mod serde_hex {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer, const N: usize>(
+        bytes: &[u8; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        let string = String::deserialize(deserializer)?;
+        let mut bytes = [0u8; N];
+        hex::decode_to_slice(string, &mut bytes).map_err(D::Error::custom)?;
+        Ok(bytes)
+    }
+}
+
+/// Serialize a JSON value without whitespace, with object keys sorted by their bytes.
+///
+/// The keys are sorted explicitly instead of relying on the iteration order of
+/// [`serde_json::Map`], which is the insertion order with serde_json's `preserve_order` feature.
+#[cfg(feature = "serde")]
+pub(crate) fn sorted_json(value: &serde_json::Value) -> String {
+    let mut json = String::new();
+    write_sorted_json(value, &mut json);
+    json
+}
+
+#[cfg(feature = "serde")]
+fn write_sorted_json(value: &serde_json::Value, json: &mut String) {
+    use serde_json::Value;
+
+    match value {
+        Value::Array(values) => {
+            json.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
                 }
+                write_sorted_json(value, json);
             }
-            None => Some(pub_bytes),
-        },
-        None => match public {
-            Some(pub_hex) => {
-                let pub_bytes = hex::decode(pub_hex)?;
-                match pub_bytes.len() {
-                    32 => Some(pub_bytes[0..32].try_into().unwrap()),
-                    _ => return Err(FromHexError::WrongKeyLength),
+            json.push(']');
+        }
+        Value::Object(members) => {
+            let mut members: Vec<_> = members.iter().collect();
+            members.sort_unstable_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+            json.push('{');
+            for (i, (key, value)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
                 }
+                json.push_str(&Value::from(key.as_str()).to_string());
+                json.push(':');
+                write_sorted_json(value, json);
             }
-            None => None,
-        },
-    };
-    Ok((sec_bytes, pub_bytes))
+            json.push('}');
+        }
+        scalar => json.push_str(&scalar.to_string()),
+    }
 }
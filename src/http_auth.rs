@@ -0,0 +1,187 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use ed25519_dalek::{Signature, Signer, VerifyingKey};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    audit::{audit, KeyOperation},
+    helper::{address_bytes, public_hex_to_bytes},
+    verify_signature, HttpAuthError, NodeIdentity, VerificationMode,
+};
+
+/// Name of the header containing the signature of the client
+pub const AUTH_HEADER: &str = "x-yggdrasil-auth";
+
+/// How far the timestamp of a request may deviate from the current time, in seconds
+pub const MAX_CLOCK_SKEW: u64 = 300;
+
+/// Domain separation prefix for the signatures in [`AUTH_HEADER`]s
+const AUTH_CONTEXT: &[u8] = b"yggdrasil-keys http auth v1";
+
+/// Create the value of the [`AUTH_HEADER`] for a request.
+///
+/// The signature covers the method, the path and query of the request, and the current time, so
+/// it can only be replayed for the same request within [`MAX_CLOCK_SKEW`]. The request has to be
+/// sent from the address of the signing node.
+pub fn auth_header(identity: &NodeIdentity, method: &str, path_and_query: &str) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    auth_header_at(identity, method, path_and_query, timestamp)
+}
+
+/// Create the value of the [`AUTH_HEADER`] for a request, at the given unix timestamp.
+pub fn auth_header_at(
+    identity: &NodeIdentity,
+    method: &str,
+    path_and_query: &str,
+    timestamp: u64,
+) -> String {
+    audit(identity, KeyOperation::Sign, "auth_header_at");
+    let signature = identity
+        .signing_keys
+        .sign(&auth_message(method, path_and_query, timestamp));
+    format!(
+        "{}:{}:{}",
+        hex::encode(identity.signing_keys.verifying_key().as_bytes()),
+        timestamp,
+        hex::encode(signature.to_bytes())
+    )
+}
+
+/// An extractor authenticating requests by the yggdrasil address they were sent from.
+///
+/// The request has to carry an [`AUTH_HEADER`] created with [`auth_header`], signed by the node
+/// key belonging to the source address of the connection. The source address is taken from
+/// [`ConnectInfo`], so the server has to be started with
+/// `into_make_service_with_connect_info::<SocketAddr>()`.
+///
+/// Use it as a handler argument, or with `axum::middleware::from_extractor` to protect whole
+/// routers. Rejected requests get a `401 Unauthorized` response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct YggdrasilAuth {
+    /// The ed25519 public key of the client
+    pub public_key: [u8; 32],
+    /// The yggdrasil address of the client
+    pub address: Ipv6Addr,
+}
+
+impl YggdrasilAuth {
+    /// Authenticate a request, as the extractor does.
+    pub fn from_parts(parts: &Parts) -> Result<Self, HttpAuthError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        Self::from_parts_at(parts, now)
+    }
+
+    /// Authenticate a request at the given unix timestamp.
+    pub fn from_parts_at(parts: &Parts, now: u64) -> Result<Self, HttpAuthError> {
+        let ConnectInfo(source) = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .ok_or(HttpAuthError::MissingConnectInfo)?;
+        let header = parts
+            .headers
+            .get(AUTH_HEADER)
+            .ok_or(HttpAuthError::MissingHeader)?
+            .to_str()
+            .map_err(|_| HttpAuthError::MalformedHeader)?;
+
+        let mut fields = header.split(':');
+        let (public_hex, timestamp, signature_hex) =
+            match (fields.next(), fields.next(), fields.next(), fields.next()) {
+                (Some(public), Some(timestamp), Some(signature), None) => {
+                    (public, timestamp, signature)
+                }
+                _ => return Err(HttpAuthError::MalformedHeader),
+            };
+        let public_key =
+            public_hex_to_bytes(public_hex).map_err(|_| HttpAuthError::MalformedHeader)?;
+        let timestamp: u64 = timestamp
+            .parse()
+            .map_err(|_| HttpAuthError::MalformedHeader)?;
+        let mut signature = [0u8; 64];
+        hex::decode_to_slice(signature_hex, &mut signature)
+            .map_err(|_| HttpAuthError::MalformedHeader)?;
+
+        if timestamp.abs_diff(now) > MAX_CLOCK_SKEW {
+            return Err(HttpAuthError::Expired);
+        }
+        let address = Ipv6Addr::from(address_bytes(&public_key, &NodeIdentity::IP_PREFIX, false));
+        if source.ip() != IpAddr::V6(address) {
+            return Err(HttpAuthError::WrongAddress);
+        }
+        let path_and_query = parts
+            .uri
+            .path_and_query()
+            .map_or("/", |path_and_query| path_and_query.as_str());
+        VerifyingKey::from_bytes(&public_key)
+            .and_then(|verifying_key| {
+                verify_signature(
+                    &verifying_key,
+                    &auth_message(parts.method.as_str(), path_and_query, timestamp),
+                    &Signature::from_bytes(&signature),
+                    VerificationMode::Strict,
+                )
+            })
+            .map_err(|_| HttpAuthError::InvalidSignature)?;
+        Ok(Self {
+            public_key,
+            address,
+        })
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for YggdrasilAuth {
+    type Rejection = HttpAuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Self::from_parts(parts)
+    }
+}
+
+impl IntoResponse for HttpAuthError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            Self::MissingConnectInfo => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::UNAUTHORIZED,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+fn auth_message(method: &str, path_and_query: &str, timestamp: u64) -> Vec<u8> {
+    [
+        AUTH_CONTEXT,
+        method.as_bytes(),
+        b"\n",
+        path_and_query.as_bytes(),
+        b"\n",
+        &timestamp.to_be_bytes(),
+    ]
+    .concat()
+}
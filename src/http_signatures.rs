@@ -0,0 +1,378 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use base64ct::{Base64, Encoding};
+use ed25519_dalek::{Signature, Signer, VerifyingKey};
+use http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode, Uri};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    audit::{audit, KeyOperation},
+    verify_signature, HttpSignatureError, NodeIdentity, VerificationMode,
+};
+
+const SIGNATURE_INPUT: &str = "signature-input";
+const SIGNATURE: &str = "signature";
+
+/// The parameters of an RFC 9421 HTTP message signature.
+///
+/// Components are either derived components like `@method`, `@authority`, `@path`, `@query`,
+/// `@scheme`, `@target-uri`, `@request-target` and `@status`, or lowercase header field names.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureParams {
+    /// The components covered by the signature, in order
+    pub components: Vec<String>,
+    /// The unix timestamp the signature was created at
+    pub created: Option<u64>,
+    /// An identifier for the key, by default the hex encoded public key
+    pub key_id: Option<String>,
+    /// Whether to include `alg="ed25519"` in the parameters
+    pub alg: bool,
+}
+
+impl SignatureParams {
+    /// Parameters covering the given components, created now,
+    /// identifying the key by its hex encoded public key.
+    pub fn new<I, C>(identity: &NodeIdentity, components: I) -> Self
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<String>,
+    {
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        Self {
+            components: components.into_iter().map(Into::into).collect(),
+            created: Some(created),
+            key_id: Some(hex::encode(
+                identity.signing_keys.verifying_key().as_bytes(),
+            )),
+            alg: true,
+        }
+    }
+
+    fn serialize(&self) -> String {
+        let components: Vec<String> = self
+            .components
+            .iter()
+            .map(|component| format!("\"{}\"", component))
+            .collect();
+        let mut params = format!("({})", components.join(" "));
+        if let Some(created) = self.created {
+            params.push_str(&format!(";created={}", created));
+        }
+        if let Some(key_id) = &self.key_id {
+            params.push_str(&format!(";keyid=\"{}\"", key_id));
+        }
+        if self.alg {
+            params.push_str(";alg=\"ed25519\"");
+        }
+        params
+    }
+
+    fn parse(input: &str) -> Result<Self, HttpSignatureError> {
+        let malformed = HttpSignatureError::MalformedSignature;
+        let input = input.strip_prefix('(').ok_or(malformed.clone())?;
+        let (list, mut rest) = input.split_once(')').ok_or(malformed.clone())?;
+        let components = list
+            .split(' ')
+            .filter(|item| !item.is_empty())
+            .map(|item| unquote(item).map(str::to_owned))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(malformed.clone())?;
+        let mut params = Self {
+            components,
+            created: None,
+            key_id: None,
+            alg: false,
+        };
+        while let Some(param) = rest.strip_prefix(';') {
+            let (name, value) = param.split_once('=').ok_or(malformed.clone())?;
+            let end = match value.strip_prefix('"') {
+                Some(quoted) => quoted.find('"').ok_or(malformed.clone())? + 2,
+                None => value.find(';').unwrap_or(value.len()),
+            };
+            let (value, remainder) = value.split_at(end);
+            rest = remainder;
+            match name {
+                "created" => params.created = Some(value.parse().map_err(|_| malformed.clone())?),
+                "keyid" => params.key_id = Some(unquote(value).ok_or(malformed.clone())?.into()),
+                "alg" if value == "\"ed25519\"" => params.alg = true,
+                "alg" => return Err(HttpSignatureError::UnsupportedAlgorithm),
+                // Other parameters, like expires or nonce, are covered by the signature,
+                // but not interpreted
+                _ => {}
+            }
+        }
+        if !rest.is_empty() {
+            return Err(malformed);
+        }
+        Ok(params)
+    }
+}
+
+/// Sign a request per RFC 9421, adding the `Signature-Input` and `Signature` headers.
+///
+/// Existing signatures are kept, so messages can carry signatures with several labels.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{sign_request, verify_request, NodeIdentity, SignatureParams};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let mut request = http::Request::post("http://example.com/inbox").body(()).unwrap();
+/// let params = SignatureParams::new(&node, ["@method", "@authority", "@path"]);
+/// sign_request(&node, &mut request, "sig1", &params).unwrap();
+///
+/// let public_key = node.public_key();
+/// assert_eq!(verify_request(&request, "sig1", &public_key).unwrap(), params);
+/// ```
+pub fn sign_request<B>(
+    identity: &NodeIdentity,
+    request: &mut Request<B>,
+    label: &str,
+    params: &SignatureParams,
+) -> Result<(), HttpSignatureError> {
+    let message = MessageRef {
+        request: Some((request.method(), request.uri())),
+        status: None,
+        headers: request.headers(),
+    };
+    let (input, signature) = sign(identity, &message, label, params, "sign_request")?;
+    let headers = request.headers_mut();
+    headers.append(SIGNATURE_INPUT, input);
+    headers.append(SIGNATURE, signature);
+    Ok(())
+}
+
+/// Sign a response per RFC 9421, adding the `Signature-Input` and `Signature` headers.
+pub fn sign_response<B>(
+    identity: &NodeIdentity,
+    response: &mut Response<B>,
+    label: &str,
+    params: &SignatureParams,
+) -> Result<(), HttpSignatureError> {
+    let message = MessageRef {
+        request: None,
+        status: Some(response.status()),
+        headers: response.headers(),
+    };
+    let (input, signature) = sign(identity, &message, label, params, "sign_response")?;
+    let headers = response.headers_mut();
+    headers.append(SIGNATURE_INPUT, input);
+    headers.append(SIGNATURE, signature);
+    Ok(())
+}
+
+/// Verify the signature with the given label on a request, and return its parameters.
+///
+/// The caller has to check that the covered components and the creation time are acceptable.
+pub fn verify_request<B>(
+    request: &Request<B>,
+    label: &str,
+    public_key: &VerifyingKey,
+) -> Result<SignatureParams, HttpSignatureError> {
+    let message = MessageRef {
+        request: Some((request.method(), request.uri())),
+        status: None,
+        headers: request.headers(),
+    };
+    verify(&message, label, public_key)
+}
+
+/// Verify the signature with the given label on a response, and return its parameters.
+///
+/// The caller has to check that the covered components and the creation time are acceptable.
+pub fn verify_response<B>(
+    response: &Response<B>,
+    label: &str,
+    public_key: &VerifyingKey,
+) -> Result<SignatureParams, HttpSignatureError> {
+    let message = MessageRef {
+        request: None,
+        status: Some(response.status()),
+        headers: response.headers(),
+    };
+    verify(&message, label, public_key)
+}
+
+/// The parts of a request or response that can be covered by a signature
+struct MessageRef<'a> {
+    request: Option<(&'a Method, &'a Uri)>,
+    status: Option<StatusCode>,
+    headers: &'a HeaderMap,
+}
+
+impl MessageRef<'_> {
+    fn component(&self, name: &str) -> Result<String, HttpSignatureError> {
+        let missing = || HttpSignatureError::MissingComponent(name.to_owned());
+        let uri = self.request.map(|(_, uri)| uri);
+        let value = match name {
+            "@method" => self.request.map(|(method, _)| method.as_str().to_owned()),
+            "@authority" => uri
+                .and_then(|uri| uri.authority())
+                .map(|authority| authority.as_str().to_ascii_lowercase())
+                .or_else(|| self.header("host").map(|host| host.to_ascii_lowercase())),
+            "@scheme" => uri
+                .and_then(|uri| uri.scheme_str())
+                .map(str::to_ascii_lowercase),
+            "@target-uri" => uri.filter(|uri| uri.scheme().is_some()).map(Uri::to_string),
+            "@path" => uri.map(|uri| uri.path().to_owned()),
+            "@query" => uri.map(|uri| format!("?{}", uri.query().unwrap_or(""))),
+            "@request-target" => uri.map(|uri| {
+                uri.path_and_query()
+                    .map_or("/", |path_and_query| path_and_query.as_str())
+                    .to_owned()
+            }),
+            "@status" => self.status.map(|status| status.as_u16().to_string()),
+            name if name.starts_with('@') => None,
+            name => self.header(name),
+        };
+        value.ok_or_else(missing)
+    }
+
+    /// Header values combined as described in RFC 9421 section 2.1
+    fn header(&self, name: &str) -> Option<String> {
+        let values = self
+            .headers
+            .get_all(name)
+            .iter()
+            .map(|value| value.to_str().map(str::trim))
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+        if values.is_empty() {
+            return None;
+        }
+        Some(values.join(", "))
+    }
+
+    fn signature_base(
+        &self,
+        components: &[String],
+        params: &str,
+    ) -> Result<String, HttpSignatureError> {
+        let mut base = String::new();
+        for component in components {
+            base.push_str(&format!(
+                "\"{}\": {}\n",
+                component,
+                self.component(component)?
+            ));
+        }
+        base.push_str(&format!("\"@signature-params\": {}", params));
+        Ok(base)
+    }
+}
+
+fn sign(
+    identity: &NodeIdentity,
+    message: &MessageRef<'_>,
+    label: &str,
+    params: &SignatureParams,
+    context: &'static str,
+) -> Result<(HeaderValue, HeaderValue), HttpSignatureError> {
+    let serialized = params.serialize();
+    let base = message.signature_base(&params.components, &serialized)?;
+    audit(identity, KeyOperation::Sign, context);
+    let signature = identity.signing_keys.sign(base.as_bytes());
+    let input = format!("{}={}", label, serialized);
+    let signature = format!(
+        "{}=:{}:",
+        label,
+        Base64::encode_string(&signature.to_bytes())
+    );
+    Ok((
+        HeaderValue::from_str(&input).map_err(|_| HttpSignatureError::MalformedSignature)?,
+        HeaderValue::from_str(&signature).map_err(|_| HttpSignatureError::MalformedSignature)?,
+    ))
+}
+
+fn verify(
+    message: &MessageRef<'_>,
+    label: &str,
+    public_key: &VerifyingKey,
+) -> Result<SignatureParams, HttpSignatureError> {
+    let input = dictionary_member(message.headers, SIGNATURE_INPUT, label)?;
+    let params = SignatureParams::parse(&input)?;
+    let signature = dictionary_member(message.headers, SIGNATURE, label)?;
+    let signature = signature
+        .strip_prefix(':')
+        .and_then(|signature| signature.strip_suffix(':'))
+        .ok_or(HttpSignatureError::MalformedSignature)?;
+    let mut bytes = [0u8; 64];
+    match Base64::decode(signature, &mut bytes) {
+        Ok(decoded) if decoded.len() == 64 => {}
+        _ => return Err(HttpSignatureError::MalformedSignature),
+    }
+
+    let base = message.signature_base(&params.components, &input)?;
+    verify_signature(
+        public_key,
+        base.as_bytes(),
+        &Signature::from_bytes(&bytes),
+        VerificationMode::Strict,
+    )
+    .map_err(|_| HttpSignatureError::InvalidSignature)?;
+    Ok(params)
+}
+
+/// Find the member with the given key in a structured field dictionary spread over headers.
+fn dictionary_member(
+    headers: &HeaderMap,
+    name: &str,
+    key: &str,
+) -> Result<String, HttpSignatureError> {
+    for value in headers.get_all(name) {
+        let value = value
+            .to_str()
+            .map_err(|_| HttpSignatureError::MalformedSignature)?;
+        for member in split_members(value) {
+            if let Some((member_key, member_value)) = member.trim().split_once('=') {
+                if member_key == key {
+                    return Ok(member_value.to_owned());
+                }
+            }
+        }
+    }
+    Err(HttpSignatureError::MissingSignature(key.to_owned()))
+}
+
+/// Split a dictionary at the commas that aren't part of strings or inner lists.
+fn split_members(value: &str) -> Vec<&str> {
+    let mut members = Vec::new();
+    let (mut start, mut depth, mut quoted) = (0, 0, false);
+    for (index, character) in value.char_indices() {
+        match character {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth -= 1,
+            ',' if !quoted && depth == 0 => {
+                members.push(&value[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    members.push(&value[start..]);
+    members
+}
+
+fn unquote(value: &str) -> Option<&str> {
+    value.strip_prefix('"')?.strip_suffix('"')
+}
@@ -0,0 +1,287 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::{Signature, Signer};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+use crate::{
+    audit::{audit, KeyOperation},
+    helper::{create_secret_file, field, public_hex_to_bytes},
+    verify_signature, KeyFileError, NodeIdentity, VerificationMode,
+};
+
+/// Domain separation prefix for the self-signatures in [`KeyFile`]s
+const SELF_SIGNATURE_CONTEXT: &[u8] = b"yggdrasil-keys key file v1";
+
+const BEGIN: &str = "-----BEGIN YGGDRASIL KEY-----";
+const END: &str = "-----END YGGDRASIL KEY-----";
+
+const MISSING_FIELD: KeyFileError = KeyFileError::Malformed("missing or misplaced field");
+
+/// A node identity together with metadata, stored in a self-describing text format.
+///
+/// The metadata is signed by the node key, so it can't be changed without access to the key.
+/// A key file looks like this:
+///
+/// ```text
+/// -----BEGIN YGGDRASIL KEY-----
+/// Version: 1
+/// Created: <unix timestamp>
+/// Comment: <single line of text>
+/// Public-Key: <hex>
+/// Secret-Key: <hex>
+/// Signature: <hex>
+/// -----END YGGDRASIL KEY-----
+/// ```
+///
+/// The signature covers the context `yggdrasil-keys key file v1`, the version as four big endian
+/// bytes, the creation time as eight big endian bytes, the public key and the comment.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{KeyFile, NodeIdentity};
+///
+/// let key_file = KeyFile::new(NodeIdentity::new(&mut thread_rng()), "laptop").unwrap();
+/// let encoded = key_file.encode();
+/// let decoded = KeyFile::decode(&encoded).unwrap();
+/// assert_eq!(decoded.comment(), "laptop");
+/// ```
+pub struct KeyFile {
+    identity: NodeIdentity,
+    comment: String,
+    created: u64,
+}
+
+impl KeyFile {
+    /// The version of the format written by this crate
+    pub const VERSION: u32 = 1;
+
+    /// Wrap a node identity with a comment and the current time.
+    pub fn new(identity: NodeIdentity, comment: impl Into<String>) -> Result<Self, KeyFileError> {
+        Self::with_created(identity, comment, SystemTime::now())
+    }
+
+    /// Wrap a node identity with a comment and the given creation time.
+    ///
+    /// The creation time is stored with a precision of seconds, times before the unix epoch are
+    /// stored as the epoch.
+    pub fn with_created(
+        identity: NodeIdentity,
+        comment: impl Into<String>,
+        created: SystemTime,
+    ) -> Result<Self, KeyFileError> {
+        let comment = comment.into();
+        if comment.contains(['\n', '\r']) {
+            return Err(KeyFileError::InvalidComment);
+        }
+        let created = created
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        Ok(Self {
+            identity,
+            comment,
+            created,
+        })
+    }
+
+    /// The node identity stored in the key file
+    pub fn identity(&self) -> &NodeIdentity {
+        &self.identity
+    }
+
+    /// Unwrap the node identity stored in the key file
+    pub fn into_identity(self) -> NodeIdentity {
+        self.identity
+    }
+
+    /// The comment describing the key
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// When the key file was created
+    pub fn created(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.created)
+    }
+
+    /// Encode the key file, including the secret key.
+    pub fn encode(&self) -> Zeroizing<String> {
+        audit(&self.identity, KeyOperation::Export, "KeyFile::encode");
+        let (secret, public) = self.identity.hex_split();
+        let secret = Zeroizing::new(secret);
+        let signature = self.identity.signing_keys.sign(&self_signature_message(
+            Self::VERSION,
+            self.created,
+            &self.identity.signing_keys.verifying_key().to_bytes(),
+            &self.comment,
+        ));
+        Zeroizing::new(format!(
+            "{}\nVersion: {}\nCreated: {}\nComment: {}\nPublic-Key: {}\nSecret-Key: {}\nSignature: {}\n{}\n",
+            BEGIN,
+            Self::VERSION,
+            self.created,
+            self.comment,
+            public,
+            secret.as_str(),
+            hex::encode(signature.to_bytes()),
+            END,
+        ))
+    }
+
+    /// Decode a key file, checking that the keys belong together and the self-signature is valid.
+    pub fn decode(encoded: &str) -> Result<Self, KeyFileError> {
+        let mut lines = encoded.lines();
+        if lines.next() != Some(BEGIN) {
+            return Err(KeyFileError::Malformed("missing begin marker"));
+        }
+        let version = field(&mut lines, "Version")
+            .ok_or(MISSING_FIELD)?
+            .parse()
+            .map_err(|_| KeyFileError::Malformed("version is not a number"))?;
+        if version != Self::VERSION {
+            return Err(KeyFileError::UnsupportedVersion(version));
+        }
+        let created = field(&mut lines, "Created")
+            .ok_or(MISSING_FIELD)?
+            .parse()
+            .map_err(|_| KeyFileError::Malformed("creation time is not a number"))?;
+        if UNIX_EPOCH
+            .checked_add(Duration::from_secs(created))
+            .is_none()
+        {
+            return Err(KeyFileError::Malformed("creation time is out of range"));
+        }
+        let comment = field(&mut lines, "Comment")
+            .ok_or(MISSING_FIELD)?
+            .to_owned();
+        let public_hex = field(&mut lines, "Public-Key").ok_or(MISSING_FIELD)?;
+        let secret_hex = field(&mut lines, "Secret-Key").ok_or(MISSING_FIELD)?;
+        let mut signature = [0u8; 64];
+        hex::decode_to_slice(
+            field(&mut lines, "Signature").ok_or(MISSING_FIELD)?,
+            &mut signature,
+        )
+        .map_err(|_| KeyFileError::Malformed("signature is not 64 hex encoded bytes"))?;
+        if lines.next() != Some(END) {
+            return Err(KeyFileError::Malformed("missing end marker"));
+        }
+        if lines.any(|line| !line.is_empty()) {
+            return Err(KeyFileError::Malformed("trailing data after end marker"));
+        }
+
+        let public = public_hex_to_bytes(public_hex)?;
+        let identity = NodeIdentity::from_hex(secret_hex, Some(public_hex))?;
+        verify_signature(
+            &identity.signing_keys.verifying_key(),
+            &self_signature_message(version, created, &public, &comment),
+            &Signature::from_bytes(&signature),
+            VerificationMode::Strict,
+        )?;
+        Ok(Self {
+            identity,
+            comment,
+            created,
+        })
+    }
+
+    /// Write the key file to a new file, only readable by its owner on Unix.
+    ///
+    /// Existing files are never overwritten.
+    pub fn save(&self, path: &Path) -> Result<(), KeyFileError> {
+        let mut file = create_secret_file(path)?;
+        file.write_all(self.encode().as_bytes())?;
+        Ok(())
+    }
+
+    /// Read and decode a key file.
+    pub fn load(path: &Path) -> Result<Self, KeyFileError> {
+        let encoded = Zeroizing::new(fs::read_to_string(path)?);
+        Self::decode(&encoded)
+    }
+}
+
+/// Whether to check the permissions of secret key files before reading them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum PermissionCheck {
+    /// Like OpenSSH, refuse to read key files that the group or others have any access to, or
+    /// that aren't owned by the current user. This only has an effect on Unix.
+    #[default]
+    Strict,
+    /// Read key files regardless of their permissions.
+    Ignore,
+}
+
+impl NodeIdentity {
+    /// Read a node identity from a secret key file, refusing files with too open permissions.
+    ///
+    /// The file can either be a [`KeyFile`], or contain a hex encoded secret key or keypair as
+    /// accepted by [`NodeIdentity::from_hex`], like the files written by [`crate::write_fleet`].
+    pub fn from_secret_file(path: &Path) -> Result<Self, KeyFileError> {
+        Self::from_secret_file_with(path, PermissionCheck::default())
+    }
+
+    /// Read a node identity from a secret key file like [`NodeIdentity::from_secret_file`],
+    /// checking the permissions of the file according to the given policy.
+    pub fn from_secret_file_with(
+        path: &Path,
+        check: PermissionCheck,
+    ) -> Result<Self, KeyFileError> {
+        // Checked and read through the same handle, so the file can't be swapped in between
+        let mut file = fs::File::open(path)?;
+        let metadata = file.metadata()?;
+        #[cfg(unix)]
+        if check == PermissionCheck::Strict {
+            use std::os::unix::fs::MetadataExt;
+
+            let mode = metadata.mode() & 0o777;
+            if mode & 0o077 != 0 {
+                return Err(KeyFileError::InsecurePermissions(mode));
+            }
+            // SAFETY: getuid has no preconditions and always succeeds
+            if metadata.uid() != unsafe { libc::getuid() } {
+                return Err(KeyFileError::NotOwned(metadata.uid()));
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = check;
+
+        // Allocated up front, so no copies of the secret key are left behind when growing
+        let mut contents = Zeroizing::new(String::with_capacity(metadata.len() as usize + 1));
+        file.read_to_string(&mut contents)?;
+        if contents.starts_with(BEGIN) {
+            return Ok(KeyFile::decode(&contents)?.into_identity());
+        }
+        Ok(Self::from_hex(contents.trim(), None)?)
+    }
+}
+
+fn self_signature_message(version: u32, created: u64, public: &[u8; 32], comment: &str) -> Vec<u8> {
+    [
+        SELF_SIGNATURE_CONTEXT,
+        &version.to_be_bytes(),
+        &created.to_be_bytes(),
+        public,
+        comment.as_bytes(),
+    ]
+    .concat()
+}
@@ -17,16 +17,95 @@
  *   You should have received a copy of the GNU Affero General Public License   *
  *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
  ********************************************************************************/
+use ed25519_dalek::{
+    ed25519::signature::Keypair, Sha512, Signature, SignatureError, Signer, Verifier,
+};
+#[cfg(feature = "rand")]
 use rand_core::{CryptoRng, RngCore};
 
+use core::cmp::Ordering;
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::net::Ipv6Addr;
+#[cfg(feature = "hex")]
+use core::str::FromStr;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+#[cfg(feature = "ipnet")]
 use ipnet::Ipv6Net;
-use std::net::Ipv6Addr;
+use zeroize::Zeroizing;
 
+#[cfg(feature = "ipnet")]
+use crate::address::{subnet_for_key, NodeSubnet};
+#[cfg(all(feature = "std", feature = "hex"))]
+use crate::helper::normalize_hex;
+use crate::{
+    address::{address_for_key, AddressVersion, Network, NodeAddress, Prefix},
+    audit::{audit, KeyOperation},
+    helper::{address_bytes, eui64, inverted_leading_ones, is_degenerate_seed, mac_address},
+    verify_signature, PrefixError, ValidationError, VerificationMode,
+};
+#[cfg(feature = "hex")]
 use crate::{
-    helper::{hex_pair_to_bytes, leading_ones, strip_ones},
+    helper::{hex_pair_to_bytes, public_hex_to_bytes},
     FromHexError,
 };
 
+/// Domain separation prefix for [`NodeIdentity::sign_with_context`]
+#[cfg(feature = "std")]
+const SIGNING_CONTEXT: &[u8] = b"yggdrasil-keys signing context v1";
+
+/// How to treat public keys supplied alongside a secret key when parsing.
+///
+/// The public key is always derived from the secret key, as ed25519-dalek doesn't allow
+/// constructing key pairs without doing so. The policy decides whether supplied public keys are
+/// checked against the derived one or ignored.
+#[cfg(feature = "hex")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum PublicKeyPolicy {
+    /// Reject supplied public keys that don't match the one derived from the secret key.
+    #[default]
+    Verify,
+    /// Ignore supplied public keys, only checking their encoding. This was the behavior of
+    /// [`NodeIdentity::from_hex`] before the policy was introduced, and is useful for reading
+    /// key files written by tools that store a stale or bogus public key.
+    Trust,
+}
+
+/// How to format keys as hex, e.g. for pasting them into tools expecting a certain style.
+///
+/// The default is lowercase without a prefix, as used by yggdrasil-go.
+#[cfg(all(feature = "std", feature = "hex"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct HexFormat {
+    /// Use uppercase instead of lowercase digits
+    pub uppercase: bool,
+    /// Start with `0x`
+    pub prefixed: bool,
+}
+
+#[cfg(all(feature = "std", feature = "hex"))]
+impl HexFormat {
+    /// Encode the bytes in this format.
+    fn encode(&self, bytes: &[u8]) -> String {
+        use core::fmt::Write;
+
+        let mut encoded = String::with_capacity(2 + bytes.len() * 2);
+        if self.prefixed {
+            encoded.push_str("0x");
+        }
+        for byte in bytes {
+            // Writing to a String can't fail
+            let _ = if self.uppercase {
+                write!(encoded, "{:02X}", byte)
+            } else {
+                write!(encoded, "{:02x}", byte)
+            };
+        }
+        encoded
+    }
+}
+
 /// Represents a node in the yggdrasil network.
 ///
 /// The keys in here are as used in the reference implementation [yggdrasil-go]. In a previous
@@ -36,14 +115,13 @@ use crate::{
 ///
 /// [yggdrasil-go]: https://github.com/yggdrasil-network/yggdrasil-go
 /// [YS001: Yggdrasil Core Specification]: https://github.com/yggdrasil-network/yggdrasil-specs/blob/ys001/ys001-yggdrasil-core-specification.md
+#[derive(Clone)]
 pub struct NodeIdentity {
     /// ed25519 key pair, used as the node identity and for address generation
-    pub signing_keys: ed25519_dalek::SigningKey,
+    pub(crate) signing_keys: ed25519_dalek::SigningKey,
 }
 
 impl NodeIdentity {
-    const ADDR_BYTE: u8 = 0xfeu8;
-    const SNET_BYTE: u8 = 0x01u8;
     /// This prefix is taken from [yggdrasil-go](yggdrasil-go),
     /// it's the one currently used in the yggdrasil network,
     /// namely `200::/7`.
@@ -61,11 +139,43 @@ impl NodeIdentity {
     /// let node = NodeIdentity::new(&mut thread_rng());
     /// let address : Ipv6Addr = node.into();
     /// ```
+    ///
+    /// Requires the `rand` feature.
+    #[cfg(feature = "rand")]
     pub fn new<R: CryptoRng + RngCore>(csprng: &mut R) -> Self {
         let signing_keys = ed25519_dalek::SigningKey::generate(csprng);
         Self { signing_keys }
     }
 
+    /// Generates node identity using the operating system's CSPRNG
+    ///
+    /// ```rust
+    /// use yggdrasil_keys::NodeIdentity;
+    ///
+    /// let node = NodeIdentity::generate();
+    /// ```
+    ///
+    /// Requires the `getrandom` feature.
+    #[cfg(feature = "getrandom")]
+    pub fn generate() -> Self {
+        Self::new(&mut rand_core::OsRng)
+    }
+
+    /// Create a node identity from the 32 bytes of a secret key.
+    pub fn from_secret_bytes(secret: &[u8; 32]) -> Self {
+        let signing_keys = ed25519_dalek::SigningKey::from_bytes(secret);
+        Self { signing_keys }
+    }
+
+    /// Create a node identity from the 64 bytes of a keypair, the secret key followed by the
+    /// public key, as returned by [`NodeIdentity::to_bytes`].
+    ///
+    /// Fails if the public key doesn't belong to the secret key.
+    pub fn from_bytes(keypair: &[u8; 64]) -> Result<Self, SignatureError> {
+        let signing_keys = ed25519_dalek::SigningKey::from_keypair_bytes(keypair)?;
+        Ok(Self { signing_keys })
+    }
+
     /// Parses hexadecimally encoded keypairs.
     ///
     /// Arguments:
@@ -80,93 +190,995 @@ impl NodeIdentity {
     ///  - If you pass a keypair to the `sec_hex` argument,
     ///  and additionally a public key to the `pub_hex` argument,
     ///  the two keys will be compared. If they differ, the function returns an error.
+    ///  - If a public key is supplied, it has to belong to the secret key,
+    ///  see [`NodeIdentity::from_hex_with`] for skipping that check.
+    #[allow(clippy::doc_lazy_continuation)]
+    #[cfg(feature = "hex")]
     pub fn from_hex(sec_hex: &str, pub_hex: Option<&str>) -> Result<Self, FromHexError> {
-        let (secret, _public) = hex_pair_to_bytes(sec_hex, pub_hex)?;
+        Self::from_hex_with(sec_hex, pub_hex, PublicKeyPolicy::default())
+    }
+
+    /// Parses hexadecimally encoded keypairs like [`NodeIdentity::from_hex`],
+    /// treating supplied public keys according to the given policy.
+    #[cfg(feature = "hex")]
+    pub fn from_hex_with(
+        sec_hex: &str,
+        pub_hex: Option<&str>,
+        policy: PublicKeyPolicy,
+    ) -> Result<Self, FromHexError> {
+        let (secret, public) = hex_pair_to_bytes(sec_hex, pub_hex)?;
         let signing_keys = ed25519_dalek::SigningKey::from_bytes(&secret);
+        if let (PublicKeyPolicy::Verify, Some(public)) = (policy, public) {
+            if signing_keys.verifying_key().as_bytes() != &public {
+                return Err(FromHexError::WrongPublicKey);
+            }
+        }
         Ok(Self { signing_keys })
     }
 
+    /// Expand the secret key into the scalar and hash prefix used for signing.
+    ///
+    /// This is intended for implementing protocols on top of node keys that need access to the
+    /// underlying scalar, and is only available with the `hazmat` feature. Misusing the expanded
+    /// key, e.g. by signing with it in a non-standard way, can leak the secret key.
+    #[cfg(feature = "hazmat")]
+    pub fn expanded_secret_key(&self) -> ed25519_dalek::hazmat::ExpandedSecretKey {
+        audit(
+            self,
+            KeyOperation::Export,
+            "NodeIdentity::expanded_secret_key",
+        );
+        ed25519_dalek::hazmat::ExpandedSecretKey::from(self.signing_keys.as_bytes())
+    }
+
+    /// The underlying ed25519-dalek key pair.
+    ///
+    /// This ties code to the ed25519-dalek version used by this crate, and signing with it
+    /// bypasses the audit hook. Prefer the signing methods of [`NodeIdentity`], this is only
+    /// available with the `hazmat` feature.
+    #[cfg(feature = "hazmat")]
+    pub fn signing_keys(&self) -> &ed25519_dalek::SigningKey {
+        audit(self, KeyOperation::Export, "NodeIdentity::signing_keys");
+        &self.signing_keys
+    }
+
+    /// The ed25519 public key
+    pub fn public_key(&self) -> ed25519_dalek::VerifyingKey {
+        self.signing_keys.verifying_key()
+    }
+
+    /// The 32 bytes of the secret key.
+    ///
+    /// This is sensitive: anyone knowing these bytes can impersonate the node. The returned bytes
+    /// are zeroized when dropped.
+    pub fn secret_key_bytes(&self) -> Zeroizing<[u8; 32]> {
+        audit(self, KeyOperation::Export, "NodeIdentity::secret_key_bytes");
+        Zeroizing::new(self.signing_keys.to_bytes())
+    }
+
+    /// The 32 bytes of the public key
+    pub fn public_key_bytes(&self) -> &[u8; 32] {
+        AsRef::<ed25519_dalek::VerifyingKey>::as_ref(&self.signing_keys).as_bytes()
+    }
+
+    /// The 64 bytes of the keypair, the secret key followed by the public key.
+    ///
+    /// This is the binary equivalent of [`NodeIdentity::to_hex_joined`].
+    pub fn to_bytes(&self) -> [u8; 64] {
+        audit(self, KeyOperation::Export, "NodeIdentity::to_bytes");
+        self.signing_keys.to_keypair_bytes()
+    }
+
     /// Hex-encode the secret and public keys into a String each
+    #[cfg(all(feature = "std", feature = "hex"))]
     pub fn to_hex_split(&self) -> (String, String) {
+        audit(self, KeyOperation::Export, "NodeIdentity::to_hex_split");
+        self.hex_split()
+    }
+
+    /// Hex-encode the keypair into a combined String
+    #[cfg(all(feature = "std", feature = "hex"))]
+    pub fn to_hex_joined(&self) -> String {
+        audit(self, KeyOperation::Export, "NodeIdentity::to_hex_joined");
+        self.hex_joined()
+    }
+
+    /// Hex-encode the secret and public keys into a String each, in the given format
+    #[cfg(all(feature = "std", feature = "hex"))]
+    pub fn to_hex_split_with(&self, format: HexFormat) -> (String, String) {
+        audit(
+            self,
+            KeyOperation::Export,
+            "NodeIdentity::to_hex_split_with",
+        );
+        (
+            format.encode(self.signing_keys.as_bytes()),
+            format.encode(self.public_key_bytes()),
+        )
+    }
+
+    /// Hex-encode the keypair into a combined String, in the given format
+    #[cfg(all(feature = "std", feature = "hex"))]
+    pub fn to_hex_joined_with(&self, format: HexFormat) -> String {
+        audit(
+            self,
+            KeyOperation::Export,
+            "NodeIdentity::to_hex_joined_with",
+        );
+        let keypair = Zeroizing::new(self.signing_keys.to_keypair_bytes());
+        format.encode(&*keypair)
+    }
+
+    /// Parses hexadecimally encoded keys like [`NodeIdentity::from_hex`], after normalizing
+    /// them: surrounding and embedded whitespace and a leading `0x` are removed, and uppercase
+    /// digits are accepted.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::{HexFormat, NodeIdentity};
+    ///
+    /// let node = NodeIdentity::new(&mut thread_rng());
+    /// let format = HexFormat { uppercase: true, prefixed: true };
+    /// let pasted = format!("  {}\n", node.to_hex_joined_with(format));
+    /// assert_eq!(NodeIdentity::from_hex_lenient(&pasted, None).unwrap(), node);
+    /// ```
+    #[cfg(all(feature = "std", feature = "hex"))]
+    pub fn from_hex_lenient(sec_hex: &str, pub_hex: Option<&str>) -> Result<Self, FromHexError> {
+        let pub_hex = pub_hex.map(normalize_hex);
+        Self::from_hex(
+            &normalize_hex(sec_hex),
+            pub_hex.as_deref().map(String::as_str),
+        )
+    }
+
+    /// Like [`NodeIdentity::to_hex_split`], without reporting to the audit hook.
+    #[cfg(all(feature = "std", feature = "hex"))]
+    pub(crate) fn hex_split(&self) -> (String, String) {
         let secret_bytes = self.signing_keys.as_bytes();
         let public_key = self.signing_keys.verifying_key();
         let public_bytes = public_key.as_bytes();
         (hex::encode(secret_bytes), hex::encode(public_bytes))
     }
 
-    /// Hex-encode the keypair into a combined String
-    pub fn to_hex_joined(&self) -> String {
-        let (secret, public) = self.to_hex_split();
-        format!("{}{}", secret, public)
+    /// Like [`NodeIdentity::to_hex_joined`], without reporting to the audit hook.
+    #[cfg(all(feature = "std", feature = "hex"))]
+    pub(crate) fn hex_joined(&self) -> String {
+        let (secret, public) = self.hex_split();
+        let secret = Zeroizing::new(secret);
+        let mut joined = String::with_capacity(secret.len() + public.len());
+        joined.push_str(&secret);
+        joined.push_str(&public);
+        joined
+    }
+
+    /// Check that the public key belongs to the secret key, and that it is a canonically encoded
+    /// curve point of large order.
+    ///
+    /// Identities constructed by this crate always pass, as the public key is derived from the
+    /// secret key. This is a defense in depth check for keys loaded from storage, e.g. before
+    /// using them for a long-running node.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let derived = ed25519_dalek::SigningKey::from_bytes(self.signing_keys.as_bytes());
+        if derived.verifying_key() != self.signing_keys.verifying_key() {
+            return Err(ValidationError::WrongPublicKey);
+        }
+        self.to_public().validate()
+    }
+
+    /// Whether the secret key looks like a placeholder or the output of a broken RNG.
+    ///
+    /// Keys with seeds repeating a short pattern, like all zeroes, or counting up or down, like
+    /// `000102...1f`, are considered degenerate. Such keys are usually copied from documentation
+    /// or tests, and shouldn't be trusted. [`crate::parse_any`] rejects them.
+    pub fn is_degenerate(&self) -> bool {
+        is_degenerate_seed(self.signing_keys.as_bytes())
+    }
+
+    /// Sign a message with the node key.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::NodeIdentity;
+    ///
+    /// let node = NodeIdentity::new(&mut thread_rng());
+    /// let signature = node.sign(b"I own this node");
+    /// assert!(node.to_public().verify(b"I own this node", &signature).is_ok());
+    /// ```
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        audit(self, KeyOperation::Sign, "NodeIdentity::sign");
+        self.signing_keys.sign(msg)
+    }
+
+    /// Sign a message for the protocol identified by the context.
+    ///
+    /// The signature is over a fixed prefix, the length of the context, the context and the
+    /// message, so it can't be passed off as a signature for another context, or as a plain
+    /// signature made with [`NodeIdentity::sign`]. Contexts can be up to 255 bytes long, longer
+    /// ones are rejected.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::NodeIdentity;
+    ///
+    /// let node = NodeIdentity::new(&mut thread_rng());
+    /// let signature = node.sign_with_context(b"nodeinfo", b"{}").unwrap();
+    /// let public = node.to_public();
+    /// assert!(public.verify_with_context(b"nodeinfo", b"{}", &signature).is_ok());
+    /// assert!(public.verify_with_context(b"handshake", b"{}", &signature).is_err());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn sign_with_context(&self, ctx: &[u8], msg: &[u8]) -> Result<Signature, SignatureError> {
+        let message = context_message(ctx, msg)?;
+        audit(self, KeyOperation::Sign, "NodeIdentity::sign_with_context");
+        Ok(self.signing_keys.sign(&message))
+    }
+
+    /// Sign a message that was already hashed with SHA-512, using Ed25519ph (RFC 8032).
+    ///
+    /// This allows signing large payloads without holding them in memory, by feeding them into
+    /// the hasher in chunks. The optional context of up to 255 bytes separates the signatures of
+    /// different protocols, and has to be passed to [`PublicNodeIdentity::verify_prehashed`] as
+    /// well. Ed25519ph signatures are not interchangeable with signatures made by
+    /// [`NodeIdentity::sign`], even over the same message.
+    ///
+    /// ```rust
+    /// use ed25519_dalek::{Digest, Sha512};
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::NodeIdentity;
+    ///
+    /// let node = NodeIdentity::new(&mut thread_rng());
+    /// let mut hasher = Sha512::new();
+    /// for chunk in [b"nodeinfo ", b"chunks   "] {
+    ///     hasher.update(chunk);
+    /// }
+    /// let signature = node.sign_prehashed(hasher.clone(), Some(b"nodeinfo")).unwrap();
+    /// let public = node.to_public();
+    /// assert!(public.verify_prehashed(hasher, Some(b"nodeinfo"), &signature).is_ok());
+    /// ```
+    pub fn sign_prehashed(
+        &self,
+        prehashed: Sha512,
+        context: Option<&[u8]>,
+    ) -> Result<Signature, SignatureError> {
+        audit(self, KeyOperation::Sign, "NodeIdentity::sign_prehashed");
+        self.signing_keys.sign_prehashed(prehashed, context)
+    }
+
+    /// Compare the preference of two nodes as root of the spanning tree,
+    /// see [`PublicNodeIdentity::cmp_tree_root`].
+    pub fn cmp_tree_root(&self, other: &Self) -> Ordering {
+        self.to_public().cmp_tree_root(&other.to_public())
+    }
+
+    /// The public part of this NodeIdentity.
+    pub fn to_public(&self) -> PublicNodeIdentity {
+        PublicNodeIdentity::from(self.signing_keys.verifying_key())
     }
 
     /// The "strength" of a given NodeIdentity is the number of leading one bits set in the
     /// inverted public key.
     pub fn strength(&self) -> u32 {
-        leading_ones(self.inverted_pub_key())
+        self.to_public().strength()
     }
 
     /// Calculate the address for this NodeIdentity with the given IP prefix.
+    ///
+    /// Panics if the prefix is empty or longer than 14 bytes.
+    #[deprecated(note = "panics for invalid prefixes, use `try_address_with_prefix` instead")]
     pub fn address_with_prefix(&self, prefix: &[u8]) -> Ipv6Addr {
-        Ipv6Addr::from(self.address_bytes(prefix, false))
+        self.try_address_with_prefix(prefix)
+            .expect("invalid IP prefix")
     }
 
     /// Calculate the `/64` subnet for this NodeIdentity with the given IP prefix.
+    ///
+    /// Panics if the prefix is empty or longer than 6 bytes.
+    #[cfg(feature = "ipnet")]
+    #[deprecated(note = "panics for invalid prefixes, use `try_subnet_with_prefix` instead")]
     pub fn subnet_with_prefix(&self, prefix: &[u8]) -> Ipv6Net {
-        let addr = Ipv6Addr::from(self.address_bytes(prefix, true));
-        Ipv6Net::new(addr, 64).unwrap().trunc()
+        self.try_subnet_with_prefix(prefix)
+            .expect("invalid IP prefix")
     }
 
-    /// Clone and invert public key
-    fn inverted_pub_key(&self) -> [u8; 32] {
-        let mut inverse_public = *self.signing_keys.verifying_key().as_bytes();
-        for byte in inverse_public.iter_mut() {
-            *byte = !*byte;
+    /// Calculate the address for this NodeIdentity in the given network.
+    pub fn address_on(&self, network: &Network) -> Ipv6Addr {
+        self.to_public().address_on(network)
+    }
+
+    /// Calculate the `/64` subnet for this NodeIdentity in the given network.
+    #[cfg(feature = "ipnet")]
+    pub fn subnet_on(&self, network: &Network) -> Ipv6Net {
+        self.to_public().subnet_on(network)
+    }
+
+    /// Calculate the address for this NodeIdentity within the given prefix.
+    pub fn address_in(&self, prefix: &Prefix) -> Ipv6Addr {
+        self.to_public().address_in(prefix)
+    }
+
+    /// Calculate the `/64` subnet for this NodeIdentity within the given prefix.
+    /// Fails for prefixes longer than 6 bytes.
+    #[cfg(feature = "ipnet")]
+    pub fn subnet_in(&self, prefix: &Prefix) -> Result<Ipv6Net, PrefixError> {
+        self.to_public().subnet_in(prefix)
+    }
+
+    /// Calculate the address for this NodeIdentity with the given IP prefix,
+    /// which has to be 1 to 14 bytes long.
+    pub fn try_address_with_prefix(&self, prefix: &[u8]) -> Result<Ipv6Addr, PrefixError> {
+        self.to_public().try_address_with_prefix(prefix)
+    }
+
+    /// Calculate the `/64` subnet for this NodeIdentity with the given IP prefix,
+    /// which has to be 1 to 6 bytes long.
+    #[cfg(feature = "ipnet")]
+    pub fn try_subnet_with_prefix(&self, prefix: &[u8]) -> Result<Ipv6Net, PrefixError> {
+        self.to_public().try_subnet_with_prefix(prefix)
+    }
+
+    /// Calculate the address for this NodeIdentity with the default IP prefix.
+    pub fn address(&self) -> Ipv6Addr {
+        self.to_public().address()
+    }
+
+    /// Calculate the `/64` subnet for this NodeIdentity with the default IP prefix.
+    #[cfg(feature = "ipnet")]
+    pub fn subnet(&self) -> Ipv6Net {
+        self.to_public().subnet()
+    }
+
+    /// Calculate the address with the given host part (the lower 64 bits) within the `/64`
+    /// subnet of this NodeIdentity, e.g. for assigning addresses to containers.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::NodeIdentity;
+    ///
+    /// let node = NodeIdentity::new(&mut thread_rng());
+    /// let container = node.address_in_subnet(0x10);
+    /// assert!(node.subnet().contains(&container));
+    /// assert_eq!(container.segments()[7], 0x10);
+    /// ```
+    pub fn address_in_subnet(&self, host: u64) -> Ipv6Addr {
+        self.to_public().address_in_subnet(host)
+    }
+
+    /// Calculate the address of a device with the given MAC address within the `/64` subnet of
+    /// this NodeIdentity, using the modified EUI-64 interface identifier of the MAC address like
+    /// stateless address autoconfiguration does.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::NodeIdentity;
+    ///
+    /// let node = NodeIdentity::new(&mut thread_rng());
+    /// let device = node.subnet_address_from_mac([0x00, 0x1b, 0x21, 0x3c, 0x4d, 0x5e]);
+    /// assert_eq!(device.segments()[4..], [0x021b, 0x21ff, 0xfe3c, 0x4d5e]);
+    /// ```
+    pub fn subnet_address_from_mac(&self, mac: [u8; 6]) -> Ipv6Addr {
+        self.to_public().subnet_address_from_mac(mac)
+    }
+
+    /// Derive a stable MAC address (EUI-48) for this NodeIdentity.
+    ///
+    /// The address consists of the first six bytes of the SHA-512 hash of the public key, with
+    /// the two lowest bits of the first byte adjusted: the locally administered bit (`0x02`) is set
+    /// and the multicast bit (`0x01`) is cleared. This makes it a locally administered unicast
+    /// address, which can't collide with vendor assigned addresses.
+    pub fn mac_address(&self) -> [u8; 6] {
+        self.to_public().mac_address()
+    }
+
+    /// Calculate the address for this NodeIdentity with the default IP prefix,
+    /// using the given address scheme.
+    pub fn versioned_address(&self, version: AddressVersion) -> NodeAddress {
+        self.to_public().versioned_address(version)
+    }
+
+    /// Calculate the `/64` subnet for this NodeIdentity with the default IP prefix,
+    /// using the given address scheme.
+    #[cfg(feature = "ipnet")]
+    pub fn versioned_subnet(&self, version: AddressVersion) -> NodeSubnet {
+        self.to_public().versioned_subnet(version)
+    }
+}
+
+/// The public part of a node in the yggdrasil network.
+///
+/// Addresses, subnets and the strength only depend on the public key, so they can be calculated
+/// for peers whose secret key is unknown, e.g. from the output of `yggdrasilctl getPeers` or the
+/// `AllowedPublicKeys` of a config.
+///
+/// ```rust
+/// use yggdrasil_keys::PublicNodeIdentity;
+///
+/// let peer = PublicNodeIdentity::from_hex(
+///     "00000305eb7f19cb4506f937494ea2ebcf58e346604c0cf76be5f67271fd9a97",
+/// )
+/// .unwrap();
+/// assert_eq!(peer.strength(), 22);
+/// assert_eq!(peer.address().to_string(), "216:7d0a:4073:1a5d:7c83:645b:58ae:8a18");
+/// assert_eq!(peer.subnet().to_string(), "316:7d0a:4073:1a5d::/64");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PublicNodeIdentity {
+    /// ed25519 public key, used as the node identity and for address generation
+    public_key: ed25519_dalek::VerifyingKey,
+}
+
+impl PublicNodeIdentity {
+    /// Parses a hexadecimally encoded public key, which has to be 32 hex encoded bytes.
+    ///
+    /// Small order and non-canonically encoded keys are rejected, as other implementations
+    /// disagree on how to handle them, see [`PublicNodeIdentity::validate`].
+    #[cfg(feature = "hex")]
+    pub fn from_hex(pub_hex: &str) -> Result<Self, FromHexError> {
+        // Like in validate, bytes that are no curve point count as a non-canonical encoding
+        let public_key = ed25519_dalek::VerifyingKey::from_bytes(&public_hex_to_bytes(pub_hex)?)
+            .map_err(|_| ValidationError::NonCanonicalPublicKey)?;
+        let identity = Self { public_key };
+        identity.validate()?;
+        Ok(identity)
+    }
+
+    /// Parses a hexadecimally encoded public key like [`PublicNodeIdentity::from_hex`], after
+    /// normalizing it, see [`NodeIdentity::from_hex_lenient`].
+    #[cfg(all(feature = "std", feature = "hex"))]
+    pub fn from_hex_lenient(pub_hex: &str) -> Result<Self, FromHexError> {
+        Self::from_hex(&normalize_hex(pub_hex))
+    }
+
+    /// Hex-encode the public key
+    #[cfg(all(feature = "std", feature = "hex"))]
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.public_key.as_bytes())
+    }
+
+    /// Hex-encode the public key in the given format
+    #[cfg(all(feature = "std", feature = "hex"))]
+    pub fn to_hex_with(&self, format: HexFormat) -> String {
+        format.encode(self.public_key.as_bytes())
+    }
+
+    /// Check that the public key is a canonically encoded curve point of large order.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let encoded = CompressedEdwardsY(self.to_bytes());
+        match encoded.decompress() {
+            Some(point) if point.compress() == encoded => {}
+            _ => return Err(ValidationError::NonCanonicalPublicKey),
         }
-        inverse_public
+        if self.public_key.is_weak() {
+            return Err(ValidationError::WeakPublicKey);
+        }
+        Ok(())
     }
 
-    /// Calculate the address bytes.
-    fn address_bytes(&self, prefix: &[u8], net: bool) -> [u8; 16] {
-        // Prefix must be at most a /48 for subnets or /112 for addresses
-        assert!(prefix.len() <= if net { 6 } else { 14 });
+    /// Create a public node identity from the 32 bytes of a public key.
+    ///
+    /// Fails if the bytes are not a valid ed25519 public key. Unlike
+    /// [`PublicNodeIdentity::from_hex`], this accepts small order and non-canonically encoded
+    /// keys, use [`PublicNodeIdentity::validate`] to reject them.
+    pub fn from_bytes(public: &[u8; 32]) -> Result<Self, SignatureError> {
+        let public_key = ed25519_dalek::VerifyingKey::from_bytes(public)?;
+        Ok(Self { public_key })
+    }
 
-        // Create 16 bytes array and copy the prefix into it
-        let mut bytes: [u8; 16] = [0u8; 16];
-        bytes[0..prefix.len()].copy_from_slice(prefix);
+    /// The ed25519 public key
+    pub fn public_key(&self) -> ed25519_dalek::VerifyingKey {
+        self.public_key
+    }
 
-        // Set the last bit of the prefix to one if its a subnet,
-        // or zero if its an address
-        bytes[prefix.len() - 1] = if net {
-            bytes[prefix.len() - 1] | Self::SNET_BYTE
-        } else {
-            bytes[prefix.len() - 1] & Self::ADDR_BYTE
-        };
+    /// The 32 bytes of the public key
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.public_key.to_bytes()
+    }
 
-        // Count the leading ones in the Node ID,
-        // and strip them plus the following zero.
-        let (ones, remainder) = strip_ones(self.inverted_pub_key());
+    /// The 32 bytes of the public key, by reference
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        self.public_key.as_bytes()
+    }
 
-        // Set the next byte to the the number of ones stripped from the Node ID
-        bytes[prefix.len()] = ones as u8;
+    /// Verify a signature made with [`NodeIdentity::sign`], with the default verification
+    /// semantics.
+    pub fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), SignatureError> {
+        self.verify_with(msg, signature, VerificationMode::default())
+    }
 
-        // Set the remaining bytes until the end of the address/subnet
-        // to the remainder of the Node ID
-        let end = if net { 8 } else { 16 };
-        bytes[(prefix.len() + 1)..end].copy_from_slice(&remainder[0..(end - (prefix.len() + 1))]);
+    /// Verify a signature made with [`NodeIdentity::sign`], with the given verification
+    /// semantics.
+    pub fn verify_with(
+        &self,
+        msg: &[u8],
+        signature: &Signature,
+        mode: VerificationMode,
+    ) -> Result<(), SignatureError> {
+        verify_signature(&self.public_key, msg, signature, mode)
+    }
 
-        // Return the address bytes
-        bytes
+    /// Verify a signature made with [`NodeIdentity::sign_with_context`] for the same context,
+    /// with the default verification semantics.
+    #[cfg(feature = "std")]
+    pub fn verify_with_context(
+        &self,
+        ctx: &[u8],
+        msg: &[u8],
+        signature: &Signature,
+    ) -> Result<(), SignatureError> {
+        self.verify(&context_message(ctx, msg)?, signature)
     }
 
-    /// Calculate the address for this NodeIdentity with the default IP prefix.
+    /// Verify a signature made with [`NodeIdentity::sign_prehashed`], with the default
+    /// verification semantics.
+    pub fn verify_prehashed(
+        &self,
+        prehashed: Sha512,
+        context: Option<&[u8]>,
+        signature: &Signature,
+    ) -> Result<(), SignatureError> {
+        self.verify_prehashed_with(prehashed, context, signature, VerificationMode::default())
+    }
+
+    /// Verify a signature made with [`NodeIdentity::sign_prehashed`], with the given
+    /// verification semantics.
+    pub fn verify_prehashed_with(
+        &self,
+        prehashed: Sha512,
+        context: Option<&[u8]>,
+        signature: &Signature,
+        mode: VerificationMode,
+    ) -> Result<(), SignatureError> {
+        match mode {
+            VerificationMode::Standard => self
+                .public_key
+                .verify_prehashed(prehashed, context, signature),
+            VerificationMode::Strict => self
+                .public_key
+                .verify_prehashed_strict(prehashed, context, signature),
+        }
+    }
+
+    /// Compare the preference of two nodes as root of the spanning tree, like yggdrasil-go.
+    ///
+    /// yggdrasil-go elects the node with the lowest public key, compared byte by byte, as root.
+    /// `Ordering::Less` means that this node is preferred over the other one. Lower keys have at
+    /// least as many leading zero bits, so the root is always among the strongest nodes, but
+    /// unlike [`Ord`], this ordering doesn't consider the strength itself.
+    ///
+    /// ```rust
+    /// use std::cmp::Ordering;
+    /// use yggdrasil_keys::PublicNodeIdentity;
+    ///
+    /// let a = PublicNodeIdentity::from_hex(
+    ///     "00000305eb7f19cb4506f937494ea2ebcf58e346604c0cf76be5f67271fd9a97",
+    /// )
+    /// .unwrap();
+    /// let b = PublicNodeIdentity::from_hex(
+    ///     "000b3fa3cfcd0a3bd87fbd784b3d6fbcab1e1e97a6c41d6a4df40b4d7281ee91",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(a.cmp_tree_root(&b), Ordering::Less);
+    /// ```
+    pub fn cmp_tree_root(&self, other: &Self) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+
+    /// The "strength" of a given PublicNodeIdentity is the number of leading one bits set in the
+    /// inverted public key.
+    pub fn strength(&self) -> u32 {
+        inverted_leading_ones(self.public_key.as_bytes())
+    }
+
+    /// Calculate the address for this PublicNodeIdentity with the given IP prefix.
+    ///
+    /// Panics if the prefix is empty or longer than 14 bytes.
+    #[deprecated(note = "panics for invalid prefixes, use `try_address_with_prefix` instead")]
+    pub fn address_with_prefix(&self, prefix: &[u8]) -> Ipv6Addr {
+        self.try_address_with_prefix(prefix)
+            .expect("invalid IP prefix")
+    }
+
+    /// Calculate the `/64` subnet for this PublicNodeIdentity with the given IP prefix.
+    ///
+    /// Panics if the prefix is empty or longer than 6 bytes.
+    #[cfg(feature = "ipnet")]
+    #[deprecated(note = "panics for invalid prefixes, use `try_subnet_with_prefix` instead")]
+    pub fn subnet_with_prefix(&self, prefix: &[u8]) -> Ipv6Net {
+        self.try_subnet_with_prefix(prefix)
+            .expect("invalid IP prefix")
+    }
+
+    /// Calculate the address for this PublicNodeIdentity in the given network.
+    pub fn address_on(&self, network: &Network) -> Ipv6Addr {
+        self.address_in(&network.prefix())
+    }
+
+    /// Calculate the `/64` subnet for this PublicNodeIdentity in the given network.
+    #[cfg(feature = "ipnet")]
+    pub fn subnet_on(&self, network: &Network) -> Ipv6Net {
+        self.subnet_bytes(network.prefix().as_bytes())
+    }
+
+    /// Calculate the address for this PublicNodeIdentity within the given prefix.
+    ///
+    /// ```rust
+    /// use yggdrasil_keys::{Prefix, PublicNodeIdentity};
+    ///
+    /// let peer = PublicNodeIdentity::from_hex(
+    ///     "00000305eb7f19cb4506f937494ea2ebcf58e346604c0cf76be5f67271fd9a97",
+    /// )
+    /// .unwrap();
+    /// let prefix: Prefix = "fc00::/7".parse().unwrap();
+    /// assert_eq!(peer.address_in(&prefix).to_string(), "fc16:7d0a:4073:1a5d:7c83:645b:58ae:8a18");
+    /// assert_eq!(peer.subnet_in(&prefix).unwrap().to_string(), "fd16:7d0a:4073:1a5d::/64");
+    /// ```
+    pub fn address_in(&self, prefix: &Prefix) -> Ipv6Addr {
+        address_for_key(self.public_key.as_bytes(), prefix)
+    }
+
+    /// Calculate the `/64` subnet for this PublicNodeIdentity within the given prefix.
+    /// Fails for prefixes longer than 6 bytes.
+    #[cfg(feature = "ipnet")]
+    pub fn subnet_in(&self, prefix: &Prefix) -> Result<Ipv6Net, PrefixError> {
+        subnet_for_key(self.public_key.as_bytes(), prefix)
+    }
+
+    /// Calculate the address for this PublicNodeIdentity with the given IP prefix,
+    /// which has to be 1 to 14 bytes long.
+    pub fn try_address_with_prefix(&self, prefix: &[u8]) -> Result<Ipv6Addr, PrefixError> {
+        check_prefix(prefix, 14)?;
+        Ok(Ipv6Addr::from(self.address_bytes(prefix, false)))
+    }
+
+    /// Calculate the `/64` subnet for this PublicNodeIdentity with the given IP prefix,
+    /// which has to be 1 to 6 bytes long.
+    #[cfg(feature = "ipnet")]
+    pub fn try_subnet_with_prefix(&self, prefix: &[u8]) -> Result<Ipv6Net, PrefixError> {
+        check_prefix(prefix, 6)?;
+        Ok(self.subnet_bytes(prefix))
+    }
+
+    /// Calculate the address bytes.
+    fn address_bytes(&self, prefix: &[u8], net: bool) -> [u8; 16] {
+        address_bytes(self.public_key.as_bytes(), prefix, net)
+    }
+
+    /// Calculate the subnet, for a prefix known to be valid.
+    #[cfg(feature = "ipnet")]
+    fn subnet_bytes(&self, prefix: &[u8]) -> Ipv6Net {
+        let addr = Ipv6Addr::from(self.address_bytes(prefix, true));
+        Ipv6Net::new(addr, 64).unwrap().trunc()
+    }
+
+    /// Calculate the address for this PublicNodeIdentity with the default IP prefix.
     pub fn address(&self) -> Ipv6Addr {
-        self.address_with_prefix(&Self::IP_PREFIX)
+        Ipv6Addr::from(self.address_bytes(&NodeIdentity::IP_PREFIX, false))
     }
 
-    /// Calculate the `/64` subnet for this NodeIdentity with the default IP prefix.
+    /// Calculate the `/64` subnet for this PublicNodeIdentity with the default IP prefix.
+    #[cfg(feature = "ipnet")]
     pub fn subnet(&self) -> Ipv6Net {
-        self.subnet_with_prefix(&Self::IP_PREFIX)
+        self.subnet_bytes(&NodeIdentity::IP_PREFIX)
+    }
+
+    /// Calculate the address with the given host part (the lower 64 bits) within the `/64`
+    /// subnet of this PublicNodeIdentity.
+    pub fn address_in_subnet(&self, host: u64) -> Ipv6Addr {
+        let mut bytes = self.address_bytes(&NodeIdentity::IP_PREFIX, true);
+        bytes[8..].copy_from_slice(&host.to_be_bytes());
+        Ipv6Addr::from(bytes)
+    }
+
+    /// Calculate the address of a device with the given MAC address within the `/64` subnet of
+    /// this PublicNodeIdentity, see [`NodeIdentity::subnet_address_from_mac`].
+    pub fn subnet_address_from_mac(&self, mac: [u8; 6]) -> Ipv6Addr {
+        self.address_in_subnet(eui64(mac))
+    }
+
+    /// Derive a stable MAC address (EUI-48) for this PublicNodeIdentity,
+    /// see [`NodeIdentity::mac_address`].
+    pub fn mac_address(&self) -> [u8; 6] {
+        mac_address(self.public_key.as_bytes())
+    }
+
+    /// Calculate the address for this PublicNodeIdentity with the default IP prefix,
+    /// using the given address scheme.
+    pub fn versioned_address(&self, version: AddressVersion) -> NodeAddress {
+        match version {
+            AddressVersion::V0_4 => NodeAddress::new(self.address(), version),
+        }
+    }
+
+    /// Calculate the `/64` subnet for this PublicNodeIdentity with the default IP prefix,
+    /// using the given address scheme.
+    #[cfg(feature = "ipnet")]
+    pub fn versioned_subnet(&self, version: AddressVersion) -> NodeSubnet {
+        match version {
+            AddressVersion::V0_4 => NodeSubnet::new(self.subnet(), version),
+        }
+    }
+}
+
+/// Verifies like [`PublicNodeIdentity::verify`], for generic code using the RustCrypto
+/// `signature` traits.
+impl Verifier<Signature> for PublicNodeIdentity {
+    fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), SignatureError> {
+        PublicNodeIdentity::verify(self, msg, signature)
+    }
+}
+
+impl fmt::Debug for PublicNodeIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PublicNodeIdentity")
+            .field("public_key", &HexBytes(self.as_bytes()))
+            .field("address", &self.address())
+            .finish()
+    }
+}
+
+/// Orders identities by [`PublicNodeIdentity::strength`], weakest first, and by the bytes of the
+/// public key if the strengths are equal.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::NodeIdentity;
+///
+/// let mut candidates: Vec<_> = (0..8).map(|_| NodeIdentity::new(&mut thread_rng())).collect();
+/// candidates.sort();
+/// let strongest = candidates.last().unwrap();
+/// assert!(candidates.iter().all(|node| node.strength() <= strongest.strength()));
+/// ```
+impl PartialOrd for PublicNodeIdentity {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PublicNodeIdentity {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.strength()
+            .cmp(&other.strength())
+            .then_with(|| self.as_bytes().cmp(other.as_bytes()))
+    }
+}
+
+/// Check that a prefix is neither empty nor longer than the given number of bytes.
+fn check_prefix(prefix: &[u8], max: usize) -> Result<(), PrefixError> {
+    match prefix.len() {
+        0 => Err(PrefixError::Empty),
+        length if length > max => Err(PrefixError::TooLong { length, max }),
+        _ => Ok(()),
+    }
+}
+
+/// Formats bytes as lowercase hex, without allocating.
+struct HexBytes<'a>(&'a [u8]);
+
+impl fmt::Debug for HexBytes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+/// Shows the hex encoded public key, followed by the address in parentheses.
+///
+/// ```rust
+/// use yggdrasil_keys::PublicNodeIdentity;
+///
+/// let peer = PublicNodeIdentity::from_hex(
+///     "00000305eb7f19cb4506f937494ea2ebcf58e346604c0cf76be5f67271fd9a97",
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     peer.to_string(),
+///     "00000305eb7f19cb4506f937494ea2ebcf58e346604c0cf76be5f67271fd9a97 \
+///      (216:7d0a:4073:1a5d:7c83:645b:58ae:8a18)"
+/// );
+/// ```
+impl fmt::Display for PublicNodeIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ({})", HexBytes(self.as_bytes()), self.address())
+    }
+}
+
+/// Parses the hex encoded public key, like [`PublicNodeIdentity::from_hex`].
+#[cfg(feature = "hex")]
+impl FromStr for PublicNodeIdentity {
+    type Err = FromHexError;
+
+    fn from_str(pub_hex: &str) -> Result<Self, FromHexError> {
+        Self::from_hex(pub_hex)
+    }
+}
+
+/// Accepts the 32 bytes of a public key, like [`PublicNodeIdentity::from_bytes`].
+impl TryFrom<&[u8]> for PublicNodeIdentity {
+    type Error = SignatureError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SignatureError> {
+        let public_key = ed25519_dalek::VerifyingKey::try_from(bytes)?;
+        Ok(Self { public_key })
+    }
+}
+
+impl AsRef<[u8]> for PublicNodeIdentity {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl From<ed25519_dalek::VerifyingKey> for PublicNodeIdentity {
+    fn from(public_key: ed25519_dalek::VerifyingKey) -> Self {
+        Self { public_key }
+    }
+}
+
+impl From<&NodeIdentity> for PublicNodeIdentity {
+    fn from(identity: &NodeIdentity) -> Self {
+        identity.to_public()
+    }
+}
+
+impl From<&NodeIdentity> for [u8; 32] {
+    fn from(identity: &NodeIdentity) -> [u8; 32] {
+        *identity.public_key_bytes()
+    }
+}
+
+impl From<PublicNodeIdentity> for [u8; 32] {
+    fn from(identity: PublicNodeIdentity) -> [u8; 32] {
+        identity.public_key.to_bytes()
+    }
+}
+
+impl From<&PublicNodeIdentity> for [u8; 32] {
+    fn from(identity: &PublicNodeIdentity) -> [u8; 32] {
+        identity.public_key.to_bytes()
+    }
+}
+
+impl From<PublicNodeIdentity> for Ipv6Addr {
+    fn from(identity: PublicNodeIdentity) -> Ipv6Addr {
+        identity.address()
+    }
+}
+
+#[cfg(feature = "ipnet")]
+impl From<PublicNodeIdentity> for Ipv6Net {
+    fn from(identity: PublicNodeIdentity) -> Ipv6Net {
+        identity.subnet()
+    }
+}
+
+impl From<&PublicNodeIdentity> for Ipv6Addr {
+    fn from(identity: &PublicNodeIdentity) -> Ipv6Addr {
+        identity.address()
+    }
+}
+
+#[cfg(feature = "ipnet")]
+impl From<&PublicNodeIdentity> for Ipv6Net {
+    fn from(identity: &PublicNodeIdentity) -> Ipv6Net {
+        identity.subnet()
+    }
+}
+
+/// The message signed by [`NodeIdentity::sign_with_context`]
+#[cfg(feature = "std")]
+fn context_message(ctx: &[u8], msg: &[u8]) -> Result<Vec<u8>, SignatureError> {
+    let length = u8::try_from(ctx.len()).map_err(|_| SignatureError::new())?;
+    Ok([SIGNING_CONTEXT, &[length], ctx, msg].concat())
+}
+
+/// Signs like [`NodeIdentity::sign`], for generic code using the RustCrypto `signature` traits.
+///
+/// ```rust
+/// use ed25519_dalek::{Signature, Signer, Verifier};
+/// use rand::thread_rng;
+/// use yggdrasil_keys::NodeIdentity;
+///
+/// fn sign_generic(signer: &impl Signer<Signature>) -> Signature {
+///     signer.sign(b"message")
+/// }
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let signature = sign_generic(&node);
+/// assert!(Verifier::verify(&node.to_public(), b"message", &signature).is_ok());
+/// ```
+impl Signer<Signature> for NodeIdentity {
+    fn try_sign(&self, msg: &[u8]) -> Result<Signature, SignatureError> {
+        Ok(self.sign(msg))
+    }
+}
+
+impl Keypair for NodeIdentity {
+    type VerifyingKey = PublicNodeIdentity;
+
+    fn verifying_key(&self) -> PublicNodeIdentity {
+        self.to_public()
+    }
+}
+
+/// Accepts either the 32 bytes of a secret key, like [`NodeIdentity::from_secret_bytes`], or the
+/// 64 bytes of a keypair, like [`NodeIdentity::from_bytes`].
+impl TryFrom<&[u8]> for NodeIdentity {
+    type Error = SignatureError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SignatureError> {
+        match bytes.len() {
+            32 => Ok(Self::from_secret_bytes(bytes.try_into().unwrap())),
+            64 => Self::from_bytes(bytes.try_into().unwrap()),
+            _ => Err(SignatureError::new()),
+        }
+    }
+}
+
+/// Parses the hex encoded secret key or keypair, like [`NodeIdentity::from_hex`] without a
+/// separate public key.
+///
+/// ```rust
+/// use yggdrasil_keys::NodeIdentity;
+///
+/// let node: NodeIdentity = "c752e88db1771790f6476bfd39b7f5664e4e02818455b8e9657ff063061e3049"
+///     .parse()
+///     .unwrap();
+/// assert_eq!(node.address().to_string(), "216:7d0a:4073:1a5d:7c83:645b:58ae:8a18");
+/// ```
+#[cfg(feature = "hex")]
+impl FromStr for NodeIdentity {
+    type Err = FromHexError;
+
+    fn from_str(sec_hex: &str) -> Result<Self, FromHexError> {
+        Self::from_hex(sec_hex, None)
+    }
+}
+
+/// Shows the public key and address, like [`PublicNodeIdentity`]. The secret key is never shown.
+impl fmt::Display for NodeIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_public().fmt(f)
+    }
+}
+
+/// Shows the public key and address, with the secret key replaced by `<redacted>`, so structs
+/// containing identities can derive `Debug` without leaking secrets.
+impl fmt::Debug for NodeIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodeIdentity")
+            .field("secret_key", &format_args!("<redacted>"))
+            .field("public_key", &HexBytes(self.public_key_bytes()))
+            .field("address", &self.address())
+            .finish()
+    }
+}
+
+/// Identities are equal if their public keys are. The secret keys are compared in constant time
+/// as well, so comparing identities doesn't leak timing information about the secrets.
+impl PartialEq for NodeIdentity {
+    fn eq(&self, other: &Self) -> bool {
+        self.public_key_bytes() == other.public_key_bytes()
+            && self.signing_keys == other.signing_keys
+    }
+}
+
+impl Eq for NodeIdentity {}
+
+/// Hashes only the public key, consistent with [`PartialEq`].
+impl Hash for NodeIdentity {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.public_key_bytes().hash(state);
+    }
+}
+
+/// Orders identities like their [`PublicNodeIdentity`], by strength.
+impl PartialOrd for NodeIdentity {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NodeIdentity {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_public().cmp(&other.to_public())
     }
 }
 
@@ -176,8 +1188,22 @@ impl From<NodeIdentity> for Ipv6Addr {
     }
 }
 
+#[cfg(feature = "ipnet")]
 impl From<NodeIdentity> for Ipv6Net {
     fn from(identity: NodeIdentity) -> Ipv6Net {
         identity.subnet()
     }
 }
+
+impl From<&NodeIdentity> for Ipv6Addr {
+    fn from(identity: &NodeIdentity) -> Ipv6Addr {
+        identity.address()
+    }
+}
+
+#[cfg(feature = "ipnet")]
+impl From<&NodeIdentity> for Ipv6Net {
+    fn from(identity: &NodeIdentity) -> Ipv6Net {
+        identity.subnet()
+    }
+}
@@ -17,6 +17,7 @@
  *   You should have received a copy of the GNU Affero General Public License   *
  *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
  ********************************************************************************/
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 
 //! yggdrasil-keys
@@ -25,17 +26,263 @@
 //! the yggdrasil mesh network.
 //!
 //! This crate implements:
-//!  - (de)serializing keys into hex Strings
-//!  - generating new keys
+//!  - (de)serializing keys into hex Strings, or into caller-provided buffers without allocating
+//!  - generating new keys, or deriving them from passphrases for recovery
+//!  - keeping the keys of many nodes in a single, optionally encrypted archive
+//!  - migrating keys between the config conventions of yggdrasil-go v0.4 and v0.5
 //!  - calculating Node and Tree IDs
 //!  - converting Node IDs into IPv6 addresses and subnets
+//!  - deriving short, stable IDs for labelling nodes in dashboards and logs
+//!  - rendering keys as emoji or OpenSSH style fingerprints for manual verification
+//!  - reporting what changed between identities, key files and peer databases
+//!  - generating DNS records for node addresses
+//!  - signing and verifying large inputs like firmware images as streams
+//!  - signing and verifying delegations of service names to addresses in node subnets
+//!  - generating nftables and ipset entries for node addresses
+//!  - converting node keys into Tor v3 onion addresses and back
+//!  - deriving cjdns addresses and telling overlay networks apart
+//!  - extracting node keys from the X.509 certificates of TLS peers
+//!  - reading and writing identities and peers as TOML config fragments
+//!
+//! Without the default `std` feature, the crate is `no_std` and doesn't allocate. Parsing keys,
+//! deriving addresses and checking signatures remain available.
+//!
+//! The default `hex` and `ipnet` features can be disabled as well, removing hex encoding of keys
+//! and subnets respectively. Addresses are still available as `Ipv6Addr`. Without the default
+//! `rand` feature, keys can't be generated, but parsing and converting them works as before.
+//! The `getrandom` feature adds `NodeIdentity::generate`, which uses the operating system's CSPRNG
+//! instead of a caller-supplied one. RNGs implementing the `rand_core` 0.9 traits can be used
+//! through `RandCore09`, with the `rand_core_0_9` feature.
 
+#[cfg(feature = "serde_with")]
+mod adapters;
+mod address;
+#[cfg(all(feature = "std", feature = "hex"))]
+mod archive;
+mod audit;
+#[cfg(feature = "batch")]
+mod batch;
+pub mod bits;
+#[cfg(feature = "matrix")]
+mod canonical_json;
+#[cfg(feature = "std")]
+mod challenge;
+#[cfg(feature = "cjdns")]
+mod cjdns;
+#[cfg(feature = "toml")]
+mod config;
+#[cfg(feature = "hex")]
+mod decode;
+#[cfg(feature = "std")]
+mod delegation;
+#[cfg(all(feature = "std", feature = "hex"))]
+mod diff;
+#[cfg(feature = "std")]
+mod dns;
+mod ecdh;
+#[cfg(feature = "std")]
+mod ephemeral;
 mod error;
+mod fingerprint;
+#[cfg(feature = "std")]
+mod firewall;
+mod flash;
+#[cfg(all(feature = "std", feature = "hex", feature = "ipnet"))]
+mod fleet;
 pub(crate) mod helper;
+#[cfg(feature = "axum")]
+mod http_auth;
+#[cfg(feature = "http-signatures")]
+mod http_signatures;
+#[cfg(all(feature = "std", feature = "hex"))]
+mod keyfile;
 mod keys;
+#[cfg(all(feature = "pkcs8", feature = "hex"))]
+pub mod migrate;
+#[cfg(feature = "std")]
+mod mining;
+#[cfg(feature = "tor")]
+mod onion;
+#[cfg(feature = "hex")]
+mod parse;
+#[cfg(feature = "passphrase")]
+mod passphrase;
+#[cfg(feature = "std")]
+mod peerdb;
+#[cfg(feature = "pkcs12")]
+mod pkcs12;
+#[cfg(feature = "pkcs8")]
+mod pkcs8;
+#[cfg(feature = "pool")]
+mod pool;
+pub mod prelude;
+#[cfg(feature = "rand_core_0_9")]
+mod rng;
+mod short_id;
+#[cfg(feature = "serde")]
+mod signed;
+mod signing;
+#[cfg(feature = "ssh")]
+mod ssh;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(all(feature = "std", feature = "hex", feature = "ipnet"))]
+mod summary;
+#[cfg(feature = "rayon")]
+mod verify;
+#[cfg(feature = "wireguard")]
+mod wireguard;
+#[cfg(feature = "x509")]
+mod x509;
 
-#[cfg(test)]
+#[cfg(all(
+    test,
+    feature = "std",
+    feature = "hex",
+    feature = "ipnet",
+    feature = "rand"
+))]
 mod tests;
 
+/// The version of `ed25519_dalek` used for keys and signatures
+pub use ed25519_dalek;
+/// The version of `ipnet` used for subnets
+#[cfg(feature = "ipnet")]
+pub use ipnet;
+
+#[cfg(feature = "serde_with")]
+pub use adapters::{YggPublicHex, YggSecretHex};
+pub use address::{
+    address_for_key, derive_addresses, does_address_match_key, is_yggdrasil_address,
+    strength_of_public_key, AddressInfo, AddressKind, AddressVersion, DeriveAddresses, Network,
+    NodeAddress, PartialKeyMatcher, Prefix,
+};
+#[cfg(feature = "ipnet")]
+pub use address::{
+    address_prefix_for_subnet, does_subnet_match_key, is_yggdrasil_subnet, subnet_for_address,
+    subnet_for_key, NodeSubnet, SubnetInfo,
+};
+#[cfg(all(feature = "std", feature = "hex"))]
+pub use archive::{ArchiveEntry, KeyArchive};
+#[cfg(feature = "std")]
+pub use audit::{clear_audit_hook, set_audit_hook};
+pub use audit::{KeyOperation, KeyUsage};
+#[cfg(feature = "batch")]
+pub use batch::verify_batch;
+#[cfg(feature = "matrix")]
+pub use canonical_json::{canonical_json, sign_json, verify_json};
+#[cfg(feature = "std")]
+pub use challenge::{Challenge, Response};
+#[cfg(feature = "cjdns")]
+pub use cjdns::{cjdns_address, parse_cjdns_key, Overlay};
+#[cfg(feature = "hex")]
+pub use decode::{decode_keypair, decode_public_key, decode_secret_key};
+#[cfg(feature = "std")]
+pub use delegation::ServiceDelegation;
+#[cfg(all(feature = "std", feature = "hex"))]
+pub use diff::{diff, Changed, DiffReport, Diffable};
+#[cfg(all(feature = "std", feature = "ipnet"))]
+pub use dns::reverse_zones;
+#[cfg(feature = "std")]
+pub use dns::{aaaa_records, ptr_records, reverse_name, zone_fragment, AaaaRecord, PtrRecord};
+#[cfg(feature = "std")]
+pub use ephemeral::LinkageProof;
+#[cfg(all(feature = "std", feature = "hex"))]
+pub use error::ArchiveError;
+#[cfg(feature = "x509")]
+pub use error::CertificateError;
+#[cfg(feature = "cjdns")]
+pub use error::CjdnsError;
+#[cfg(feature = "std")]
+pub use error::DelegationError;
+#[cfg(feature = "hex")]
 pub use error::FromHexError;
-pub use keys::NodeIdentity;
+#[cfg(feature = "axum")]
+pub use error::HttpAuthError;
+#[cfg(feature = "http-signatures")]
+pub use error::HttpSignatureError;
+#[cfg(feature = "matrix")]
+pub use error::JsonSignatureError;
+#[cfg(all(feature = "std", feature = "hex"))]
+pub use error::KeyFileError;
+#[cfg(all(feature = "pkcs8", feature = "hex"))]
+pub use error::MigrationError;
+#[cfg(feature = "tor")]
+pub use error::OnionError;
+#[cfg(feature = "hex")]
+pub use error::ParseError;
+#[cfg(feature = "passphrase")]
+pub use error::PassphraseError;
+#[cfg(feature = "pkcs12")]
+pub use error::Pkcs12Error;
+#[cfg(feature = "ssh")]
+pub use error::SshCertificateError;
+#[cfg(feature = "toml")]
+pub use error::TomlError;
+pub use error::{
+    AddressInfoError, BitsError, ErrorCode, FingerprintError, FlashError, KeyExchangeError,
+    PrefixError, SignatureEncodingError, ValidationError,
+};
+#[cfg(feature = "serde")]
+pub use error::{PeerDbError, SignedError};
+#[cfg(feature = "std")]
+pub use error::{StreamError, SubmissionError, WordlistError};
+pub use fingerprint::{emoji_fingerprint, Emoji, Fingerprint, EMOJI_FINGERPRINT_LEN};
+#[cfg(feature = "std")]
+pub use firewall::{ipset_restore, nft_elements, FilterEntries};
+pub use flash::{FLASH_MAGIC, FLASH_MAX_LEN, FLASH_VERSION};
+#[cfg(all(feature = "std", feature = "hex", feature = "ipnet"))]
+pub use fleet::{derive_fleet, derive_fleet_node, read_fleet_seed, write_fleet, INVENTORY_FILE};
+#[cfg(feature = "axum")]
+pub use http_auth::{auth_header, auth_header_at, YggdrasilAuth, AUTH_HEADER, MAX_CLOCK_SKEW};
+#[cfg(feature = "http-signatures")]
+pub use http_signatures::{
+    sign_request, sign_response, verify_request, verify_response, SignatureParams,
+};
+#[cfg(all(feature = "std", feature = "hex"))]
+pub use keyfile::{KeyFile, PermissionCheck};
+#[cfg(all(feature = "std", feature = "hex"))]
+pub use keys::HexFormat;
+#[cfg(feature = "hex")]
+pub use keys::PublicKeyPolicy;
+pub use keys::{NodeIdentity, PublicNodeIdentity};
+#[cfg(all(feature = "std", feature = "rand"))]
+pub use mining::ReplayRng;
+#[cfg(feature = "metrics")]
+pub use mining::{
+    describe_miner_metrics, METRIC_ATTEMPTS, METRIC_BEST_STRENGTH, METRIC_KEYS_PER_SECOND,
+    METRIC_WORKERS,
+};
+#[cfg(feature = "std")]
+pub use mining::{Coordinator, Miner, StepResult, Submission, Wordlist, WorkConfig};
+#[cfg(feature = "tor")]
+pub use onion::{onion_address, public_key_from_onion};
+#[cfg(feature = "hex")]
+pub use parse::{parse_any, ParsedKey, MAX_PARSE_LEN};
+#[cfg(feature = "passphrase")]
+pub use passphrase::PassphraseParams;
+#[cfg(feature = "std")]
+pub use peerdb::{PeerDb, PeerRecord};
+#[cfg(feature = "pool")]
+pub use pool::{Acquire, KeyPool};
+#[cfg(feature = "rand_core_0_9")]
+pub use rng::RandCore09;
+pub use short_id::ShortId;
+#[cfg(feature = "serde")]
+pub use signed::Signed;
+pub use signing::{check_signature_encoding, verify_signature, VerificationMode};
+#[cfg(feature = "ssh")]
+pub use ssh::{verify_ssh_certificate, verify_ssh_certificate_at};
+#[cfg(feature = "std")]
+pub use stream::verify_reader;
+#[cfg(all(feature = "std", feature = "hex", feature = "ipnet"))]
+pub use summary::IdentitySummary;
+#[cfg(feature = "rayon")]
+pub use verify::{verify_pairs, Verdict};
+#[cfg(feature = "wireguard")]
+pub use wireguard::{derive_from_seed, WireGuardKeypair};
+#[cfg(feature = "x509")]
+pub use x509::{
+    public_key_from_certificate_der, public_key_from_certificate_der_with,
+    public_key_from_certificate_pem, public_key_from_certificate_pem_with, CertificateCheck,
+};
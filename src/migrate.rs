@@ -0,0 +1,255 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+//! Moving node keys between yggdrasil-go config conventions.
+//!
+//! yggdrasil-go v0.4 keeps the hex encoded keypair in the `PrivateKey` setting of its config,
+//! while v0.5 can also read a PKCS#8 PEM file referenced by `PrivateKeyPath`. The functions in
+//! here convert between the two, rewriting only the affected line of the (H)JSON config, so
+//! comments and formatting are preserved.
+//!
+//! ```rust
+//! use rand::thread_rng;
+//! use yggdrasil_keys::{migrate, NodeIdentity};
+//!
+//! let node = NodeIdentity::new(&mut thread_rng());
+//! let config = format!("{{\n  PrivateKey: {}\n  IfName: auto\n}}\n", node.to_hex_joined());
+//!
+//! let externalized = migrate::externalize_key(&config, "/etc/yggdrasil/key.pem").unwrap();
+//! assert!(externalized.config.contains("  PrivateKeyPath: /etc/yggdrasil/key.pem\n"));
+//!
+//! let inlined = migrate::inline_key(&externalized.config, &externalized.pem).unwrap();
+//! assert_eq!(*inlined, config);
+//! ```
+//!
+//! Requires the `pkcs8` feature.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+use crate::{
+    audit::{audit, KeyOperation},
+    helper::{create_secret_file, replace_secret_file},
+    MigrationError, NodeIdentity,
+};
+
+/// The config setting containing the hex encoded keypair
+pub const PRIVATE_KEY: &str = "PrivateKey";
+/// The config setting containing the path of a PKCS#8 PEM file
+pub const PRIVATE_KEY_PATH: &str = "PrivateKeyPath";
+
+/// A config with its key moved into a separate PEM file, see [`externalize_key`].
+pub struct ExternalizedKey {
+    /// The config, referencing the key file instead of containing the key
+    pub config: String,
+    /// The PKCS#8 PEM encoded key, to be stored at the referenced path
+    pub pem: Zeroizing<String>,
+}
+
+/// A `Key: value` line in a config
+struct Setting<'a> {
+    line: usize,
+    indent: &'a str,
+    quoted_key: bool,
+    value: Zeroizing<String>,
+    quoted_value: bool,
+    trailing: &'a str,
+}
+
+impl Setting<'_> {
+    fn render(&self, key: &str, value: &str) -> String {
+        let quote = |quoted: bool| if quoted { "\"" } else { "" };
+        let value = if self.quoted_value {
+            value.replace('\\', "\\\\").replace('"', "\\\"")
+        } else {
+            value.to_owned()
+        };
+        format!(
+            "{indent}{q}{key}{q}: {vq}{value}{vq}{trailing}",
+            indent = self.indent,
+            q = quote(self.quoted_key),
+            vq = quote(self.quoted_value),
+            trailing = self.trailing,
+        )
+    }
+}
+
+/// Move the `PrivateKey` of a config into a PEM file at `key_path`.
+///
+/// The `PrivateKey` setting is replaced by a `PrivateKeyPath` setting referencing `key_path`.
+/// Writing the returned PEM to that path is up to the caller, see [`externalize_key_file`] for
+/// doing both.
+pub fn externalize_key(config: &str, key_path: &str) -> Result<ExternalizedKey, MigrationError> {
+    let (setting, lines) = take_setting(config, PRIVATE_KEY, PRIVATE_KEY_PATH)?;
+    let identity = NodeIdentity::from_hex(&setting.value, None)?;
+    audit(&identity, KeyOperation::Export, "migrate::externalize_key");
+    Ok(ExternalizedKey {
+        config: replace_line(
+            &lines,
+            &setting,
+            &setting.render(PRIVATE_KEY_PATH, key_path),
+        ),
+        pem: identity.pkcs8_pem()?,
+    })
+}
+
+/// Move the key from the PEM file referenced by the `PrivateKeyPath` of a config back into a
+/// `PrivateKey` setting.
+///
+/// `pem` has to be the contents of the key file, see [`key_path`] for finding it, and
+/// [`inline_key_file`] for doing both.
+pub fn inline_key(config: &str, pem: &str) -> Result<Zeroizing<String>, MigrationError> {
+    let (setting, lines) = take_setting(config, PRIVATE_KEY_PATH, PRIVATE_KEY)?;
+    let identity = NodeIdentity::from_pkcs8_pem(pem)?;
+    audit(&identity, KeyOperation::Export, "migrate::inline_key");
+    let line = Zeroizing::new(setting.render(PRIVATE_KEY, &identity.hex_joined()));
+    Ok(Zeroizing::new(replace_line(&lines, &setting, &line)))
+}
+
+/// The path of the key file referenced by the `PrivateKeyPath` of a config, if any.
+pub fn key_path(config: &str) -> Option<String> {
+    let lines: Vec<&str> = config.split_inclusive('\n').collect();
+    find_setting(&lines, PRIVATE_KEY_PATH)
+        .ok()
+        .flatten()
+        .filter(|setting| !setting.value.is_empty())
+        .map(|setting| setting.value.to_string())
+}
+
+/// Move the `PrivateKey` of a config file into a new PEM file at `key_path`, like
+/// [`externalize_key`].
+///
+/// The key file is only readable by its owner on Unix, and existing files are never overwritten.
+/// The config file is replaced once the key file is written.
+pub fn externalize_key_file(config_path: &Path, key_path: &Path) -> Result<(), MigrationError> {
+    let config = Zeroizing::new(fs::read_to_string(config_path)?);
+    let reference = key_path.to_str().ok_or(MigrationError::NonUnicodePath)?;
+    let externalized = externalize_key(&config, reference)?;
+    create_secret_file(key_path)?.write_all(externalized.pem.as_bytes())?;
+    replace_secret_file(config_path, externalized.config.as_bytes())?;
+    Ok(())
+}
+
+/// Move the key from the PEM file referenced by a config file back into the config, like
+/// [`inline_key`].
+///
+/// Relative key paths are resolved against the current directory, like yggdrasil-go does. The
+/// config file is replaced by one only readable by its owner on Unix, the key file is left as is.
+pub fn inline_key_file(config_path: &Path) -> Result<(), MigrationError> {
+    let config = Zeroizing::new(fs::read_to_string(config_path)?);
+    let key_path = key_path(&config).ok_or(MigrationError::MissingSetting(PRIVATE_KEY_PATH))?;
+    let pem = Zeroizing::new(fs::read_to_string(key_path)?);
+    replace_secret_file(config_path, inline_key(&config, &pem)?.as_bytes())?;
+    Ok(())
+}
+
+/// Find the setting `key`, making sure there's no conflicting non-empty `other` setting.
+///
+/// Empty `other` settings are removed from the returned lines.
+fn take_setting<'a>(
+    config: &'a str,
+    key: &'static str,
+    other: &'static str,
+) -> Result<(Setting<'a>, Vec<&'a str>), MigrationError> {
+    let mut lines: Vec<&str> = config.split_inclusive('\n').collect();
+    if let Some(conflict) = find_setting(&lines, other)? {
+        if !conflict.value.is_empty() {
+            return Err(MigrationError::ConflictingSettings);
+        }
+        lines.remove(conflict.line);
+    }
+    let setting = find_setting(&lines, key)?.ok_or(MigrationError::MissingSetting(key))?;
+    Ok((setting, lines))
+}
+
+/// Find the only line setting `key`.
+fn find_setting<'a>(
+    lines: &[&'a str],
+    key: &'static str,
+) -> Result<Option<Setting<'a>>, MigrationError> {
+    let mut found = None;
+    for (index, line) in lines.iter().enumerate() {
+        if let Some(setting) = parse_setting(index, line, key) {
+            if found.is_some() {
+                return Err(MigrationError::DuplicateSetting(key));
+            }
+            found = Some(setting);
+        }
+    }
+    Ok(found)
+}
+
+fn parse_setting<'a>(index: usize, line: &'a str, key: &str) -> Option<Setting<'a>> {
+    let content = line.trim_end_matches(['\r', '\n']);
+    let rest = content.trim_start();
+    let indent = &content[..content.len() - rest.len()];
+    let (quoted_key, rest) = match rest.strip_prefix('"') {
+        Some(rest) => (true, rest.strip_prefix(key)?.strip_prefix('"')?),
+        None => (false, rest.strip_prefix(key)?),
+    };
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    let Some(quoted) = rest.strip_prefix('"') else {
+        return Some(Setting {
+            line: index,
+            indent,
+            quoted_key,
+            value: Zeroizing::new(rest.trim_end().to_owned()),
+            quoted_value: false,
+            trailing: "",
+        });
+    };
+    let mut value = Zeroizing::new(String::new());
+    let mut chars = quoted.char_indices();
+    while let Some((position, char)) = chars.next() {
+        match char {
+            '"' => {
+                return Some(Setting {
+                    line: index,
+                    indent,
+                    quoted_key,
+                    value,
+                    quoted_value: true,
+                    trailing: &quoted[position + 1..],
+                })
+            }
+            '\\' => value.push(chars.next()?.1),
+            _ => value.push(char),
+        }
+    }
+    None
+}
+
+fn replace_line(lines: &[&str], setting: &Setting<'_>, replacement: &str) -> String {
+    let original = lines[setting.line];
+    let ending = &original[original.trim_end_matches(['\r', '\n']).len()..];
+    // Allocated up front, so no copies of secret keys are left behind when growing
+    let length = lines.iter().map(|line| line.len()).sum::<usize>() + replacement.len();
+    let mut config = String::with_capacity(length);
+    for (index, line) in lines.iter().enumerate() {
+        if index == setting.line {
+            config.push_str(replacement);
+            config.push_str(ending);
+        } else {
+            config.push_str(line);
+        }
+    }
+    config
+}
@@ -0,0 +1,642 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::{Signature, Signer, Verifier, VerifyingKey};
+#[cfg(feature = "rand")]
+use rand_core::{impls, CryptoRng, Error, RngCore};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use std::net::Ipv6Addr;
+use std::time::Duration;
+#[cfg(feature = "rand")]
+use std::time::Instant;
+#[cfg(feature = "rand")]
+use zeroize::Zeroize;
+
+use crate::{
+    audit::{audit, KeyOperation},
+    helper::inverted_leading_ones,
+    NodeIdentity, SubmissionError, WordlistError,
+};
+
+/// Domain separation prefix for the proofs in [`Submission`]s
+const PROOF_CONTEXT: &[u8] = b"yggdrasil-keys mining submission v1";
+
+/// Name of the counter of generated keys, see [`describe_miner_metrics`]
+#[cfg(feature = "metrics")]
+pub const METRIC_ATTEMPTS: &str = "yggdrasil_keys_miner_attempts_total";
+/// Name of the gauge of the current key generation rate, see [`describe_miner_metrics`]
+#[cfg(feature = "metrics")]
+pub const METRIC_KEYS_PER_SECOND: &str = "yggdrasil_keys_miner_keys_per_second";
+/// Name of the gauge of the highest strength found, see [`describe_miner_metrics`]
+#[cfg(feature = "metrics")]
+pub const METRIC_BEST_STRENGTH: &str = "yggdrasil_keys_miner_best_strength";
+/// Name of the gauge of existing miners, see [`describe_miner_metrics`]
+#[cfg(feature = "metrics")]
+pub const METRIC_WORKERS: &str = "yggdrasil_keys_miner_workers";
+
+/// Register descriptions for the metrics recorded by [`Miner`]s.
+///
+/// With the `metrics` feature, miners record their progress via the [`metrics`] facade, so they
+/// can be exported with any `metrics` recorder, e.g. for Prometheus:
+///  - [`METRIC_ATTEMPTS`]: counter of generated keys
+///  - [`METRIC_KEYS_PER_SECOND`]: gauge of the rate of the last [`Miner::mine`] call
+///  - [`METRIC_BEST_STRENGTH`]: gauge of the highest strength found by any miner
+///  - [`METRIC_WORKERS`]: gauge of the number of miners in existence
+///
+/// Call this once after installing the recorder.
+#[cfg(feature = "metrics")]
+pub fn describe_miner_metrics() {
+    use metrics::{describe_counter, describe_gauge};
+
+    describe_counter!(METRIC_ATTEMPTS, "Number of keys generated by miners");
+    describe_gauge!(
+        METRIC_KEYS_PER_SECOND,
+        "Keys generated per second during the last mining run"
+    );
+    describe_gauge!(METRIC_BEST_STRENGTH, "Highest strength found by miners");
+    describe_gauge!(METRIC_WORKERS, "Number of miners");
+}
+
+/// Highest strength found by any miner in this process, backing [`METRIC_BEST_STRENGTH`]
+#[cfg(feature = "metrics")]
+static BEST_STRENGTH: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+#[cfg(feature = "metrics")]
+fn record_best_strength(strength: u32) {
+    let previous = BEST_STRENGTH.fetch_max(strength, std::sync::atomic::Ordering::Relaxed);
+    if strength > previous {
+        metrics::gauge!(METRIC_BEST_STRENGTH).set(strength as f64);
+    }
+}
+
+/// The period over which the duty cycle of throttled miners is enforced
+#[cfg(feature = "rand")]
+const THROTTLE_PERIOD: Duration = Duration::from_millis(100);
+/// The number of keys generated between checks of the duty cycle
+#[cfg(feature = "rand")]
+const THROTTLE_INTERVAL: u64 = 64;
+
+/// Domain separation prefix for the output blocks of [`ReplayRng`]s
+#[cfg(feature = "rand")]
+const REPLAY_CONTEXT: &[u8] = b"yggdrasil-keys replay rng v1";
+
+/// A deterministic RNG for reproducible mining runs, like benchmarks.
+///
+/// The output is the SHA-512 hash of the seed and a block counter, so the same seed always
+/// produces the same keys. Only use it with secret, random seeds for keys that are put to use.
+///
+/// ```rust
+/// use yggdrasil_keys::{Miner, ReplayRng};
+///
+/// let mut first = Miner::new(4).with_transcript();
+/// let mut second = Miner::new(4).with_transcript();
+/// first.mine(&mut ReplayRng::new([7; 32]), 100_000).unwrap();
+/// second.mine(&mut ReplayRng::new([7; 32]), 100_000).unwrap();
+/// assert_eq!(first.attempts(), second.attempts());
+/// assert_eq!(first.transcript(), second.transcript());
+/// ```
+#[cfg(feature = "rand")]
+pub struct ReplayRng {
+    seed: [u8; 32],
+    counter: u64,
+    block: [u8; 64],
+    position: usize,
+}
+
+#[cfg(feature = "rand")]
+impl ReplayRng {
+    /// Create an RNG producing the sequence for the given seed.
+    pub fn new(seed: [u8; 32]) -> Self {
+        Self {
+            seed,
+            counter: 0,
+            block: [0; 64],
+            position: 64,
+        }
+    }
+}
+
+#[cfg(feature = "rand")]
+impl RngCore for ReplayRng {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest {
+            if self.position == self.block.len() {
+                let block = Sha512::new()
+                    .chain_update(REPLAY_CONTEXT)
+                    .chain_update(self.seed)
+                    .chain_update(self.counter.to_be_bytes())
+                    .finalize();
+                self.block.copy_from_slice(&block);
+                self.counter += 1;
+                self.position = 0;
+            }
+            *byte = self.block[self.position];
+            self.position += 1;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rand")]
+impl CryptoRng for ReplayRng {}
+
+#[cfg(feature = "rand")]
+impl Drop for ReplayRng {
+    fn drop(&mut self) {
+        self.seed.zeroize();
+        self.block.zeroize();
+    }
+}
+
+/// Hex-expressible words to look for in addresses, see [`Miner::with_wordlist`].
+///
+/// Words are matched against the hex digits of the address after the `2` prefix, ignoring the
+/// colons, so a word can span two groups like `dead:beef`. Words may contain digits, like
+/// `c0ffee`, and are case insensitive.
+///
+/// ```rust
+/// use yggdrasil_keys::Wordlist;
+///
+/// let wordlist = Wordlist::new(["dead", "beef", "cafe"]).unwrap();
+/// assert_eq!(wordlist.find("200:1234:cafe::1".parse().unwrap()), Some("cafe"));
+/// assert_eq!(wordlist.find("200:1234::1".parse().unwrap()), None);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Wordlist {
+    /// The words, in the order they were supplied, together with their nibbles
+    words: Vec<(String, Vec<u8>)>,
+}
+
+impl Wordlist {
+    /// Create a wordlist, checking that every word consists of hex digits only.
+    pub fn new<I, S>(words: I) -> Result<Self, WordlistError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let words = words
+            .into_iter()
+            .map(|word| {
+                let word = word.as_ref().to_ascii_lowercase();
+                let nibbles = word
+                    .chars()
+                    .map(|char| char.to_digit(16).map(|digit| digit as u8))
+                    .collect::<Option<Vec<u8>>>()
+                    .filter(|nibbles| (1..=30).contains(&nibbles.len()))
+                    .ok_or_else(|| WordlistError::InvalidWord(word.clone()))?;
+                Ok((word, nibbles))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if words.is_empty() {
+            return Err(WordlistError::Empty);
+        }
+        Ok(Self { words })
+    }
+
+    /// The first word of the list contained in the address, if any.
+    pub fn find(&self, address: Ipv6Addr) -> Option<&str> {
+        let mut nibbles = [0u8; 30];
+        for (index, byte) in address.octets()[1..].iter().enumerate() {
+            nibbles[2 * index] = byte >> 4;
+            nibbles[2 * index + 1] = byte & 0xf;
+        }
+        self.words
+            .iter()
+            .find(|(_, word)| {
+                nibbles
+                    .windows(word.len())
+                    .any(|window| window == &word[..])
+            })
+            .map(|(word, _)| word.as_str())
+    }
+
+    /// The words, in the order they were supplied
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.words.iter().map(|(word, _)| word.as_str())
+    }
+}
+
+/// The outcome of a [`Miner::step`].
+// Boxing the identity would only save copying it once, at the end of the search
+#[allow(clippy::large_enum_variant)]
+pub enum StepResult {
+    /// An identity reaching the minimum strength was found
+    Found(NodeIdentity),
+    /// The time budget ran out, contains the number of keys generated in this step
+    Pending(u64),
+}
+
+/// Searches for node identities with a minimum strength by generating random keys.
+pub struct Miner {
+    min_strength: u32,
+    attempts: u64,
+    best_strength: Option<u32>,
+    duty_cycle: u8,
+    #[cfg(feature = "rand")]
+    busy: Duration,
+    throttled: Duration,
+    transcript: Option<Sha512>,
+    wordlist: Option<Wordlist>,
+}
+
+impl Miner {
+    /// Create a miner looking for identities with at least the given strength.
+    pub fn new(min_strength: u32) -> Self {
+        #[cfg(feature = "metrics")]
+        metrics::gauge!(METRIC_WORKERS).increment(1.0);
+        Self {
+            min_strength,
+            attempts: 0,
+            best_strength: None,
+            duty_cycle: 100,
+            #[cfg(feature = "rand")]
+            busy: Duration::ZERO,
+            throttled: Duration::ZERO,
+            transcript: None,
+            wordlist: None,
+        }
+    }
+
+    /// Record a transcript of all public keys generated by this miner.
+    ///
+    /// Together with a [`ReplayRng`], this allows checking that different mining backends
+    /// went through exactly the same keys when comparing their performance.
+    pub fn with_transcript(mut self) -> Self {
+        self.transcript = Some(Sha512::new());
+        self
+    }
+
+    /// Only accept identities whose address contains a word from the wordlist.
+    ///
+    /// Found identities still have to reach the minimum strength. Use [`Wordlist::find`] on the
+    /// address of a found identity to learn which word matched. Every additional hex digit of a
+    /// word makes it 16 times harder to find, words of up to five digits are found in seconds.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::{Miner, Wordlist};
+    ///
+    /// let wordlist = Wordlist::new(["ace", "bad", "fed"]).unwrap();
+    /// let mut miner = Miner::new(0).with_wordlist(wordlist.clone());
+    /// let node = miner.mine(&mut thread_rng(), 100_000).unwrap();
+    /// println!("{} contains {}", node.address(), wordlist.find(node.address()).unwrap());
+    /// ```
+    pub fn with_wordlist(mut self, wordlist: Wordlist) -> Self {
+        self.wordlist = Some(wordlist);
+        self
+    }
+
+    /// Limit the miner to run the given percentage of the time, sleeping in between.
+    ///
+    /// This keeps background mining from pegging fans and draining batteries. The percentage is
+    /// clamped to `1..=100`, with 100 meaning no throttling. To lower the priority of mining
+    /// threads instead, use the facilities of the operating system, like `nice`.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::Miner;
+    ///
+    /// let mut miner = Miner::new(4).with_duty_cycle(50);
+    /// let node = miner.mine(&mut thread_rng(), 100_000).unwrap();
+    /// assert!(node.strength() >= 4);
+    /// ```
+    pub fn with_duty_cycle(mut self, percent: u8) -> Self {
+        self.duty_cycle = percent.clamp(1, 100);
+        self
+    }
+
+    /// Generate up to `max_attempts` keys using the supplied CSPRNG,
+    /// returning the first one that reaches the minimum strength.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::Miner;
+    ///
+    /// let mut miner = Miner::new(4);
+    /// let node = miner.mine(&mut thread_rng(), 100_000).unwrap();
+    /// assert!(node.strength() >= 4);
+    /// ```
+    ///
+    /// Requires the `rand` feature.
+    #[cfg(feature = "rand")]
+    pub fn mine<R: CryptoRng + RngCore>(
+        &mut self,
+        csprng: &mut R,
+        max_attempts: u64,
+    ) -> Option<NodeIdentity> {
+        #[cfg(feature = "metrics")]
+        let (start, attempts_before) = (std::time::Instant::now(), self.attempts);
+        let mut found = None;
+        let mut slice_start = Instant::now();
+        for _ in 0..max_attempts {
+            if self.duty_cycle < 100 && self.attempts % THROTTLE_INTERVAL == 0 {
+                self.busy += slice_start.elapsed();
+                self.throttle();
+                slice_start = Instant::now();
+            }
+            let identity = NodeIdentity::new(csprng);
+            if let Some(transcript) = &mut self.transcript {
+                transcript.update(identity.signing_keys.verifying_key().as_bytes());
+            }
+            let strength = identity.strength();
+            self.attempts += 1;
+            if self.best_strength.map_or(true, |best| strength > best) {
+                self.best_strength = Some(strength);
+                #[cfg(feature = "metrics")]
+                record_best_strength(strength);
+            }
+            if strength >= self.min_strength
+                && self
+                    .wordlist
+                    .as_ref()
+                    .map_or(true, |wordlist| wordlist.find(identity.address()).is_some())
+            {
+                found = Some(identity);
+                break;
+            }
+        }
+        if self.duty_cycle < 100 {
+            self.busy += slice_start.elapsed();
+        }
+        #[cfg(feature = "metrics")]
+        {
+            let attempts = self.attempts - attempts_before;
+            metrics::counter!(METRIC_ATTEMPTS).increment(attempts);
+            let elapsed = start.elapsed().as_secs_f64();
+            if elapsed > 0.0 {
+                metrics::gauge!(METRIC_KEYS_PER_SECOND).set(attempts as f64 / elapsed);
+            }
+        }
+        found
+    }
+
+    /// Mine for roughly the given time budget, so mining can be driven from an event loop.
+    ///
+    /// The clock is checked between small batches of keys, so the budget can be overrun by the
+    /// time needed for one batch. Each step generates at least one batch, even with a zero budget.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use std::time::Duration;
+    /// use yggdrasil_keys::{Miner, StepResult};
+    ///
+    /// let mut miner = Miner::new(4);
+    /// let node = loop {
+    ///     match miner.step(&mut thread_rng(), Duration::from_millis(10)) {
+    ///         StepResult::Found(node) => break node,
+    ///         // Handle other events here
+    ///         StepResult::Pending(_) => {}
+    ///     }
+    /// };
+    /// assert!(node.strength() >= 4);
+    /// ```
+    ///
+    /// Requires the `rand` feature.
+    #[cfg(feature = "rand")]
+    pub fn step<R: CryptoRng + RngCore>(&mut self, csprng: &mut R, budget: Duration) -> StepResult {
+        let start = Instant::now();
+        let attempts_before = self.attempts;
+        loop {
+            if let Some(identity) = self.mine(csprng, THROTTLE_INTERVAL) {
+                return StepResult::Found(identity);
+            }
+            if start.elapsed() >= budget {
+                return StepResult::Pending(self.attempts - attempts_before);
+            }
+        }
+    }
+
+    /// Sleep if the miner used up its share of the current period.
+    #[cfg(feature = "rand")]
+    fn throttle(&mut self) {
+        let budget = THROTTLE_PERIOD * u32::from(self.duty_cycle) / 100;
+        if self.busy >= budget {
+            let pause = THROTTLE_PERIOD - budget;
+            std::thread::sleep(pause);
+            self.throttled += pause;
+            self.busy -= budget;
+        }
+    }
+
+    /// The minimum strength this miner is looking for
+    pub fn min_strength(&self) -> u32 {
+        self.min_strength
+    }
+
+    /// The number of keys generated so far
+    pub fn attempts(&self) -> u64 {
+        self.attempts
+    }
+
+    /// The highest strength of all keys generated so far
+    pub fn best_strength(&self) -> Option<u32> {
+        self.best_strength
+    }
+
+    /// The duty cycle of this miner in percent
+    pub fn duty_cycle(&self) -> u8 {
+        self.duty_cycle
+    }
+
+    /// The SHA-512 hash of all public keys generated so far, in order,
+    /// if enabled with [`Miner::with_transcript`]
+    pub fn transcript(&self) -> Option<[u8; 64]> {
+        self.transcript
+            .as_ref()
+            .map(|transcript| transcript.clone().finalize().into())
+    }
+
+    /// The wordlist addresses have to match, if set with [`Miner::with_wordlist`]
+    pub fn wordlist(&self) -> Option<&Wordlist> {
+        self.wordlist.as_ref()
+    }
+
+    /// The time this miner spent sleeping to stay within its duty cycle
+    pub fn throttled(&self) -> Duration {
+        self.throttled
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for Miner {
+    fn drop(&mut self) {
+        metrics::gauge!(METRIC_WORKERS).decrement(1.0);
+    }
+}
+
+/// A key hunt handed out to workers by a [`Coordinator`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WorkConfig {
+    /// Random identifier of the hunt, binding submissions to it
+    pub id: u64,
+    /// The minimum strength of keys worth submitting
+    pub min_strength: u32,
+}
+
+impl WorkConfig {
+    /// Create a miner for this work.
+    pub fn miner(&self) -> Miner {
+        Miner::new(self.min_strength)
+    }
+}
+
+/// A key found by a worker, submitted to a [`Coordinator`].
+///
+/// The submission contains the public key and a signature over the work id, proving that the
+/// worker has the secret key without revealing it. Workers keep the secret keys, so the
+/// coordinator has to request the secret key of the best submission from its worker separately.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Submission {
+    /// The id of the work this key was found for
+    pub work_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::helper::serde_hex"))]
+    public_key: [u8; 32],
+    #[cfg_attr(feature = "serde", serde(with = "crate::helper::serde_hex"))]
+    proof: [u8; 64],
+}
+
+impl Submission {
+    /// Create a submission for an identity found for the given work.
+    pub fn new(work: &WorkConfig, identity: &NodeIdentity) -> Self {
+        audit(identity, KeyOperation::Sign, "Submission::new");
+        let proof = identity.signing_keys.sign(&proof_message(work.id));
+        Self {
+            work_id: work.id,
+            public_key: *identity.signing_keys.verifying_key().as_bytes(),
+            proof: proof.to_bytes(),
+        }
+    }
+
+    /// The ed25519 public key of the found identity
+    pub fn public_key(&self) -> &[u8; 32] {
+        &self.public_key
+    }
+
+    /// The strength of the found identity
+    pub fn strength(&self) -> u32 {
+        inverted_leading_ones(&self.public_key)
+    }
+
+    /// Check that the submission meets the requirements of the work,
+    /// and that the proof is valid.
+    pub fn verify(&self, work: &WorkConfig) -> Result<(), SubmissionError> {
+        if self.work_id != work.id {
+            return Err(SubmissionError::WrongWork);
+        }
+        let strength = self.strength();
+        if strength < work.min_strength {
+            return Err(SubmissionError::TooWeak {
+                strength,
+                min_strength: work.min_strength,
+            });
+        }
+        let public_key = VerifyingKey::from_bytes(&self.public_key)?;
+        public_key.verify(
+            &proof_message(self.work_id),
+            &Signature::from_bytes(&self.proof),
+        )?;
+        Ok(())
+    }
+}
+
+/// Hands out work to workers, and keeps track of the best verified submission.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{Coordinator, Submission};
+///
+/// let mut coordinator = Coordinator::new(&mut thread_rng(), 4);
+///
+/// // On each worker:
+/// let work = coordinator.work();
+/// let node = work.miner().mine(&mut thread_rng(), 100_000).unwrap();
+/// let submission = Submission::new(&work, &node);
+///
+/// // Back on the coordinator:
+/// assert!(coordinator.submit(submission).unwrap());
+/// assert!(coordinator.best().is_some());
+/// ```
+pub struct Coordinator {
+    work: WorkConfig,
+    best: Option<Submission>,
+}
+
+impl Coordinator {
+    /// Start a new hunt for keys with at least the given strength.
+    ///
+    /// The CSPRNG is used to generate the work id.
+    ///
+    /// Requires the `rand` feature.
+    #[cfg(feature = "rand")]
+    pub fn new<R: CryptoRng + RngCore>(csprng: &mut R, min_strength: u32) -> Self {
+        Self {
+            work: WorkConfig {
+                id: csprng.next_u64(),
+                min_strength,
+            },
+            best: None,
+        }
+    }
+
+    /// The work to hand out to workers
+    pub fn work(&self) -> WorkConfig {
+        self.work
+    }
+
+    /// Verify and record a submission.
+    ///
+    /// Returns whether the submission is stronger than all previous ones.
+    pub fn submit(&mut self, submission: Submission) -> Result<bool, SubmissionError> {
+        submission.verify(&self.work)?;
+        let is_best = self
+            .best
+            .as_ref()
+            .map_or(true, |best| submission.strength() > best.strength());
+        if is_best {
+            self.best = Some(submission);
+        }
+        Ok(is_best)
+    }
+
+    /// The strongest submission so far
+    pub fn best(&self) -> Option<&Submission> {
+        self.best.as_ref()
+    }
+}
+
+/// The message signed by the proofs in [`Submission`]s
+fn proof_message(work_id: u64) -> Vec<u8> {
+    [PROOF_CONTEXT, &work_id.to_be_bytes()].concat()
+}
@@ -0,0 +1,147 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::VerifyingKey;
+use sha2::{Digest, Sha512};
+use sha3::Sha3_256;
+use zeroize::Zeroizing;
+
+use crate::{
+    audit::{audit, KeyOperation},
+    NodeIdentity, OnionError,
+};
+
+/// The onion service version encoded in v3 addresses
+const ONION_VERSION: u8 = 3;
+/// The domain separator for the checksum in v3 addresses
+const CHECKSUM_PREFIX: &[u8] = b".onion checksum";
+/// The header of the `hs_ed25519_secret_key` file used by tor
+const SECRET_KEY_HEADER: &[u8; 32] = b"== ed25519v1-secret: type0 ==\0\0\0";
+/// The header of the `hs_ed25519_public_key` file used by tor
+const PUBLIC_KEY_HEADER: &[u8; 32] = b"== ed25519v1-public: type0 ==\0\0\0";
+/// The lowercase RFC 4648 base32 alphabet
+const BASE32: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+impl NodeIdentity {
+    /// The Tor v3 onion address of the node key, including the `.onion` suffix.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::{public_key_from_onion, NodeIdentity};
+    ///
+    /// let node = NodeIdentity::new(&mut thread_rng());
+    /// let onion = node.onion_address();
+    /// assert_eq!(public_key_from_onion(&onion).unwrap(), node.public_key());
+    /// ```
+    pub fn onion_address(&self) -> String {
+        onion_address(&self.signing_keys.verifying_key())
+    }
+
+    /// The contents of the `hs_ed25519_secret_key` file for running an onion service with
+    /// the node key.
+    ///
+    /// Tor stores the expanded secret key, so node keys can't be recovered from these files.
+    pub fn tor_secret_key_file(&self) -> Zeroizing<Vec<u8>> {
+        audit(
+            self,
+            KeyOperation::Export,
+            "NodeIdentity::tor_secret_key_file",
+        );
+        let mut expanded = Zeroizing::new([0u8; 64]);
+        expanded.copy_from_slice(&Sha512::digest(self.signing_keys.as_bytes()));
+        expanded[0] &= 248;
+        expanded[31] &= 127;
+        expanded[31] |= 64;
+        let mut file = Zeroizing::new(Vec::with_capacity(96));
+        file.extend_from_slice(SECRET_KEY_HEADER);
+        file.extend_from_slice(&*expanded);
+        file
+    }
+
+    /// The contents of the `hs_ed25519_public_key` file for running an onion service with
+    /// the node key.
+    pub fn tor_public_key_file(&self) -> Vec<u8> {
+        let mut file = Vec::with_capacity(64);
+        file.extend_from_slice(PUBLIC_KEY_HEADER);
+        file.extend_from_slice(self.signing_keys.verifying_key().as_bytes());
+        file
+    }
+}
+
+/// The Tor v3 onion address of an ed25519 public key, including the `.onion` suffix.
+pub fn onion_address(public_key: &VerifyingKey) -> String {
+    let mut bytes = [0u8; 35];
+    bytes[0..32].copy_from_slice(public_key.as_bytes());
+    bytes[32..34].copy_from_slice(&checksum(public_key.as_bytes(), ONION_VERSION));
+    bytes[34] = ONION_VERSION;
+
+    let mut address = String::with_capacity(62);
+    for chunk in bytes.chunks(5) {
+        let bits = chunk
+            .iter()
+            .fold(0u64, |bits, &byte| (bits << 8) | u64::from(byte));
+        for index in (0..8).rev() {
+            address.push(char::from(BASE32[((bits >> (index * 5)) & 0x1f) as usize]));
+        }
+    }
+    address.push_str(".onion");
+    address
+}
+
+/// Parse a Tor v3 onion address, with or without the `.onion` suffix, into its public key.
+///
+/// Yggdrasil addresses can then be derived from the public key as usual.
+pub fn public_key_from_onion(address: &str) -> Result<VerifyingKey, OnionError> {
+    let address = address.strip_suffix(".onion").unwrap_or(address);
+    if address.len() != 56 {
+        return Err(OnionError::Malformed);
+    }
+
+    let mut bytes = [0u8; 35];
+    for (chunk, output) in address.as_bytes().chunks(8).zip(bytes.chunks_mut(5)) {
+        let mut bits = 0u64;
+        for character in chunk {
+            let value = BASE32
+                .iter()
+                .position(|symbol| *symbol == character.to_ascii_lowercase())
+                .ok_or(OnionError::Malformed)?;
+            bits = (bits << 5) | value as u64;
+        }
+        output.copy_from_slice(&bits.to_be_bytes()[3..8]);
+    }
+
+    if bytes[34] != ONION_VERSION {
+        return Err(OnionError::UnsupportedVersion(bytes[34]));
+    }
+    let mut public_key = [0u8; 32];
+    public_key.copy_from_slice(&bytes[0..32]);
+    if bytes[32..34] != checksum(&public_key, ONION_VERSION) {
+        return Err(OnionError::WrongChecksum);
+    }
+    Ok(VerifyingKey::from_bytes(&public_key)?)
+}
+
+fn checksum(public_key: &[u8; 32], version: u8) -> [u8; 2] {
+    let hash = Sha3_256::new()
+        .chain_update(CHECKSUM_PREFIX)
+        .chain_update(public_key)
+        .chain_update([version])
+        .finalize();
+    [hash[0], hash[1]]
+}
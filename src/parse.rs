@@ -0,0 +1,181 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use core::convert::TryFrom;
+
+#[cfg(feature = "pkcs8")]
+use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use zeroize::Zeroizing;
+
+#[cfg(all(feature = "std", feature = "hex"))]
+use crate::KeyFile;
+use crate::{NodeIdentity, ParseError, PublicNodeIdentity};
+
+/// The longest input [`parse_any`] looks at, which is plenty for every supported encoding
+pub const MAX_PARSE_LEN: usize = 8192;
+
+/// A key parsed by [`parse_any`].
+pub enum ParsedKey {
+    /// A secret key, together with its public key
+    Identity(NodeIdentity),
+    /// Only a public key
+    PublicKey(VerifyingKey),
+}
+
+impl ParsedKey {
+    /// The public key, derived from the secret key if necessary.
+    pub fn public_key(&self) -> VerifyingKey {
+        match self {
+            Self::Identity(identity) => identity.signing_keys.verifying_key(),
+            Self::PublicKey(public_key) => *public_key,
+        }
+    }
+
+    /// The node identity, if a secret key was parsed.
+    pub fn into_identity(self) -> Option<NodeIdentity> {
+        match self {
+            Self::Identity(identity) => Some(identity),
+            Self::PublicKey(_) => None,
+        }
+    }
+}
+
+/// Parse a key from untrusted input in any of the supported encodings.
+///
+/// The encoding is detected from the input:
+///  - 128 hex characters are a keypair, as in yggdrasil-go configs,
+///    and 64 hex characters are a public key
+///  - 64 bytes are a binary keypair, and 32 bytes are a binary public key
+///  - PEM encoded PKCS#8 private keys and SPKI public keys, as well as DER encoded ones,
+///    if the `pkcs8` feature is enabled
+///  - [`KeyFile`]s, if the `std` feature is enabled
+///
+/// Surrounding whitespace is ignored for the text encodings. Keypairs have to belong together,
+/// public keys have to be valid curve points, and secret keys must not be degenerate, see
+/// [`NodeIdentity::is_degenerate`]. This never panics, no matter the input.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{parse_any, NodeIdentity, ParseError};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let config = format!("{}\n", node.to_hex_joined());
+/// let parsed = parse_any(config.as_bytes()).unwrap();
+/// assert_eq!(parsed.public_key(), node.public_key());
+/// assert!(parsed.into_identity().is_some());
+///
+/// assert_eq!(parse_any(b"not a key").err(), Some(ParseError::UnknownFormat));
+/// ```
+pub fn parse_any(input: &[u8]) -> Result<ParsedKey, ParseError> {
+    match parse_untrusted(input)? {
+        ParsedKey::Identity(identity) if identity.is_degenerate() => {
+            Err(ParseError::DegenerateSecret)
+        }
+        parsed => Ok(parsed),
+    }
+}
+
+fn parse_untrusted(input: &[u8]) -> Result<ParsedKey, ParseError> {
+    if input.len() > MAX_PARSE_LEN {
+        return Err(ParseError::TooLong(input.len()));
+    }
+    if let Some(text) = core::str::from_utf8(input).ok().map(str::trim) {
+        if text.starts_with("-----BEGIN ") {
+            return parse_armored(text);
+        }
+        if !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return parse_hex(text);
+        }
+    }
+    parse_binary(input)
+}
+
+fn parse_hex(text: &str) -> Result<ParsedKey, ParseError> {
+    match text.len() {
+        64 => {
+            let mut public_key = [0u8; 32];
+            hex::decode_to_slice(text, &mut public_key).map_err(|_| ParseError::InvalidKey)?;
+            parse_public_key(&public_key)
+        }
+        128 => {
+            let mut keypair = Zeroizing::new([0u8; 64]);
+            hex::decode_to_slice(text, &mut *keypair).map_err(|_| ParseError::InvalidKey)?;
+            parse_keypair(&keypair)
+        }
+        _ => Err(ParseError::UnknownFormat),
+    }
+}
+
+fn parse_binary(input: &[u8]) -> Result<ParsedKey, ParseError> {
+    if let Ok(public_key) = <&[u8; 32]>::try_from(input) {
+        return parse_public_key(public_key);
+    }
+    if let Ok(keypair) = <&[u8; 64]>::try_from(input) {
+        return parse_keypair(keypair);
+    }
+    #[cfg(feature = "pkcs8")]
+    if input.first() == Some(&0x30) {
+        if let Ok(signing_keys) = SigningKey::from_pkcs8_der(input) {
+            return Ok(ParsedKey::Identity(NodeIdentity { signing_keys }));
+        }
+        if let Ok(public_key) = VerifyingKey::from_public_key_der(input) {
+            return Ok(ParsedKey::PublicKey(public_key));
+        }
+        return Err(ParseError::MalformedPem);
+    }
+    Err(ParseError::UnknownFormat)
+}
+
+fn parse_armored(text: &str) -> Result<ParsedKey, ParseError> {
+    let label = text
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("-----BEGIN "))
+        .and_then(|line| line.strip_suffix("-----"));
+    match label {
+        #[cfg(feature = "pkcs8")]
+        Some("PRIVATE KEY") => SigningKey::from_pkcs8_pem(text)
+            .map(|signing_keys| ParsedKey::Identity(NodeIdentity { signing_keys }))
+            .map_err(|_| ParseError::MalformedPem),
+        #[cfg(feature = "pkcs8")]
+        Some("PUBLIC KEY") => VerifyingKey::from_public_key_pem(text)
+            .map(ParsedKey::PublicKey)
+            .map_err(|_| ParseError::MalformedPem),
+        Some("ENCRYPTED PRIVATE KEY") => Err(ParseError::Encrypted),
+        #[cfg(all(feature = "std", feature = "hex"))]
+        Some("YGGDRASIL KEY") => Ok(ParsedKey::Identity(KeyFile::decode(text)?.into_identity())),
+        _ => Err(ParseError::UnknownFormat),
+    }
+}
+
+/// Parse a public key, rejecting small order and non-canonically encoded keys.
+fn parse_public_key(public_key: &[u8; 32]) -> Result<ParsedKey, ParseError> {
+    VerifyingKey::from_bytes(public_key)
+        .ok()
+        .filter(|key| PublicNodeIdentity::from(*key).validate().is_ok())
+        .map(ParsedKey::PublicKey)
+        .ok_or(ParseError::InvalidKey)
+}
+
+fn parse_keypair(keypair: &[u8; 64]) -> Result<ParsedKey, ParseError> {
+    SigningKey::from_keypair_bytes(keypair)
+        .map(|signing_keys| ParsedKey::Identity(NodeIdentity { signing_keys }))
+        .map_err(|_| ParseError::InvalidKey)
+}
@@ -0,0 +1,111 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use argon2::{Algorithm, Argon2, Params, Version};
+use zeroize::Zeroizing;
+
+use crate::{NodeIdentity, PassphraseError};
+
+/// Argon2id cost parameters for [`NodeIdentity::from_passphrase`].
+///
+/// The parameters are part of what has to be remembered to recover an identity: the same
+/// passphrase and salt give different identities with different parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PassphraseParams {
+    /// Memory size in KiB
+    pub memory_kib: u32,
+    /// Number of passes over the memory
+    pub iterations: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl PassphraseParams {
+    /// The second recommended option of RFC 9106: 64 MiB of memory, three passes and four lanes.
+    pub const RFC_9106_LOW_MEMORY: Self = Self {
+        memory_kib: 64 * 1024,
+        iterations: 3,
+        parallelism: 4,
+    };
+
+    /// The first recommended option of RFC 9106: 2 GiB of memory, one pass and four lanes.
+    pub const RFC_9106: Self = Self {
+        memory_kib: 2 * 1024 * 1024,
+        iterations: 1,
+        parallelism: 4,
+    };
+}
+
+/// Uses [`PassphraseParams::RFC_9106_LOW_MEMORY`], which works on most machines.
+impl Default for PassphraseParams {
+    fn default() -> Self {
+        Self::RFC_9106_LOW_MEMORY
+    }
+}
+
+impl NodeIdentity {
+    /// Derive a node identity from a passphrase, for recovering keys when only a memorized phrase
+    /// survives.
+    ///
+    /// **This is dangerous.** Anyone who guesses the passphrase gets the node key, and unlike
+    /// passwords protecting a key file, the derived public key is published, so guesses can be
+    /// checked offline at any rate an attacker can afford. Only use this with long, randomly
+    /// generated passphrases, like six or more words from a diceware list, never with passwords
+    /// chosen by people. Keys from [`NodeIdentity::new`] should be preferred wherever they can be
+    /// backed up.
+    ///
+    /// The seed of the key is the 32 byte Argon2id (version 0x13) hash of the passphrase with
+    /// the salt, so the key can be recovered with any Argon2 implementation. The salt has to be
+    /// at least 8 bytes long; using a different salt per node, like its hostname, keeps attackers
+    /// from attacking several nodes at once.
+    ///
+    /// Requires the `passphrase` feature.
+    ///
+    /// ```rust
+    /// use yggdrasil_keys::{NodeIdentity, PassphraseParams};
+    ///
+    /// let params = PassphraseParams::default();
+    /// let passphrase = "correct horse battery staple ocean tundra";
+    /// let node = NodeIdentity::from_passphrase(passphrase, b"node1.example", params).unwrap();
+    /// let again = NodeIdentity::from_passphrase(passphrase, b"node1.example", params).unwrap();
+    /// assert_eq!(node.address(), again.address());
+    /// ```
+    pub fn from_passphrase(
+        passphrase: impl AsRef<[u8]>,
+        salt: &[u8],
+        params: PassphraseParams,
+    ) -> Result<Self, PassphraseError> {
+        let passphrase = passphrase.as_ref();
+        if passphrase.is_empty() {
+            return Err(PassphraseError::EmptyPassphrase);
+        }
+        let params = Params::new(
+            params.memory_kib,
+            params.iterations,
+            params.parallelism,
+            Some(32),
+        )?;
+        let mut seed = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase, salt, &mut *seed)?;
+        Ok(Self {
+            signing_keys: ed25519_dalek::SigningKey::from_bytes(&seed),
+        })
+    }
+}
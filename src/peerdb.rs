@@ -0,0 +1,222 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::net::Ipv6Addr;
+use std::time::SystemTime;
+#[cfg(feature = "serde")]
+use std::{fs, path::Path};
+
+use crate::{helper::address_bytes, NodeIdentity};
+#[cfg(feature = "serde")]
+use crate::{helper::replace_file, PeerDbError};
+
+/// What is known about a peer in a [`PeerDb`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PeerRecord {
+    /// The ed25519 public key of the peer
+    #[cfg_attr(feature = "serde", serde(with = "crate::helper::serde_hex"))]
+    pub public_key: [u8; 32],
+    address: Ipv6Addr,
+    /// When the peer was first observed
+    pub first_seen: SystemTime,
+    /// When the peer was last observed
+    pub last_seen: SystemTime,
+    /// A free form note about the peer
+    pub note: Option<String>,
+}
+
+impl PeerRecord {
+    /// The address derived from the public key, with the default IP prefix
+    pub fn address(&self) -> Ipv6Addr {
+        self.address
+    }
+
+    /// The network part of the routed `/64` subnet of the peer
+    pub fn subnet_address(&self) -> Ipv6Addr {
+        Ipv6Addr::from(address_bytes(
+            &self.public_key,
+            &NodeIdentity::IP_PREFIX,
+            true,
+        ))
+    }
+}
+
+/// A store of observed peers, e.g. for network crawlers.
+///
+/// With the `serde` feature, the database can be saved to and loaded from JSON files.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{NodeIdentity, PeerDb};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let mut db = PeerDb::new();
+/// db.observe(node.public_key().to_bytes()).note = Some("seed node".into());
+/// let record = db.by_address(node.address()).unwrap();
+/// assert_eq!(record.note.as_deref(), Some("seed node"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PeerDb {
+    /// Sorted by public key
+    peers: Vec<PeerRecord>,
+}
+
+impl PeerDb {
+    /// Create an empty database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that a peer was seen now, creating a record for it if it's new.
+    pub fn observe(&mut self, public_key: [u8; 32]) -> &mut PeerRecord {
+        self.observe_at(public_key, SystemTime::now())
+    }
+
+    /// Record that a peer was seen at the given time, creating a record for it if it's new.
+    ///
+    /// Observations may arrive out of order, the first and last seen times are only ever widened.
+    pub fn observe_at(&mut self, public_key: [u8; 32], time: SystemTime) -> &mut PeerRecord {
+        let index = match self.position(&public_key) {
+            Ok(index) => {
+                let record = &mut self.peers[index];
+                record.first_seen = record.first_seen.min(time);
+                record.last_seen = record.last_seen.max(time);
+                index
+            }
+            Err(index) => {
+                self.peers.insert(
+                    index,
+                    PeerRecord {
+                        public_key,
+                        address: node_address(&public_key),
+                        first_seen: time,
+                        last_seen: time,
+                        note: None,
+                    },
+                );
+                index
+            }
+        };
+        &mut self.peers[index]
+    }
+
+    /// Look up a peer by its public key.
+    pub fn get(&self, public_key: &[u8; 32]) -> Option<&PeerRecord> {
+        self.position(public_key)
+            .ok()
+            .map(|index| &self.peers[index])
+    }
+
+    /// Look up a peer by its public key, for editing its record.
+    pub fn get_mut(&mut self, public_key: &[u8; 32]) -> Option<&mut PeerRecord> {
+        self.position(public_key)
+            .ok()
+            .map(move |index| &mut self.peers[index])
+    }
+
+    /// Remove a peer from the database.
+    pub fn remove(&mut self, public_key: &[u8; 32]) -> Option<PeerRecord> {
+        self.position(public_key)
+            .ok()
+            .map(|index| self.peers.remove(index))
+    }
+
+    /// Look up a peer by its node address.
+    pub fn by_address(&self, address: Ipv6Addr) -> Option<&PeerRecord> {
+        self.peers.iter().find(|record| record.address == address)
+    }
+
+    /// Look up the peer whose routed `/64` subnet contains the given address.
+    pub fn by_subnet(&self, address: Ipv6Addr) -> Option<&PeerRecord> {
+        self.peers
+            .iter()
+            .find(|record| record.subnet_address().octets()[0..8] == address.octets()[0..8])
+    }
+
+    /// All peers, ordered by public key.
+    pub fn iter(&self) -> impl Iterator<Item = &PeerRecord> {
+        self.peers.iter()
+    }
+
+    /// The number of peers in the database
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Whether the database contains no peers
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// Write the database to a JSON file.
+    ///
+    /// The file is written next to the destination first and then moved into place, so an
+    /// interrupted save doesn't destroy the previous database.
+    #[cfg(feature = "serde")]
+    pub fn save(&self, path: &Path) -> Result<(), PeerDbError> {
+        replace_file(path, &serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Read a database from a JSON file.
+    #[cfg(feature = "serde")]
+    pub fn load(path: &Path) -> Result<Self, PeerDbError> {
+        let mut db: Self = serde_json::from_slice(&fs::read(path)?)?;
+        db.normalize();
+        Ok(db)
+    }
+
+    /// Restore the invariants after deserializing.
+    ///
+    /// Addresses are derived from the public keys again, and duplicate records are merged like
+    /// repeated observations, keeping the first note.
+    #[cfg(feature = "serde")]
+    pub(crate) fn normalize(&mut self) {
+        // Files might have been edited by hand
+        for record in &mut self.peers {
+            record.address = node_address(&record.public_key);
+        }
+        self.peers.sort_by_key(|record| record.public_key);
+        self.peers.dedup_by(|duplicate, record| {
+            if duplicate.public_key != record.public_key {
+                return false;
+            }
+            record.first_seen = record.first_seen.min(duplicate.first_seen);
+            record.last_seen = record.last_seen.max(duplicate.last_seen);
+            if record.note.is_none() {
+                record.note = duplicate.note.take();
+            }
+            true
+        });
+    }
+
+    fn position(&self, public_key: &[u8; 32]) -> Result<usize, usize> {
+        self.peers
+            .binary_search_by(|record| record.public_key.cmp(public_key))
+    }
+}
+
+/// The address of a peer, with the default IP prefix
+fn node_address(public_key: &[u8; 32]) -> Ipv6Addr {
+    Ipv6Addr::from(address_bytes(public_key, &NodeIdentity::IP_PREFIX, false))
+}
@@ -0,0 +1,44 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::{pkcs8::DecodePrivateKey, SigningKey};
+use p12_keystore::Pkcs12Archive;
+
+use crate::{NodeIdentity, Pkcs12Error};
+
+/// The object identifier of ed25519 keys, as defined in RFC 8410
+const ED25519_OID: &str = "1.3.101.112";
+
+impl NodeIdentity {
+    /// Read a node identity from a PKCS#12 (`.p12`/`.pfx`) bundle.
+    ///
+    /// The first ed25519 private key in the bundle is used, any other keys and the certificates
+    /// are ignored. Requires the `pkcs12` feature.
+    pub fn from_pkcs12(data: &[u8], password: &str) -> Result<Self, Pkcs12Error> {
+        let archive = Pkcs12Archive::from_pkcs12(data, password)?;
+        let bag = archive
+            .keys
+            .iter()
+            .find(|bag| bag.key.oid().to_string() == ED25519_OID)
+            .ok_or(Pkcs12Error::NoEd25519Key)?;
+        let signing_keys =
+            SigningKey::from_pkcs8_der(bag.key.as_der()).map_err(Pkcs12Error::InvalidKey)?;
+        Ok(Self { signing_keys })
+    }
+}
@@ -0,0 +1,119 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::{
+    pkcs8::{DecodePrivateKey, EncodePrivateKey, KeypairBytes},
+    SigningKey,
+};
+use pkcs8::{
+    der::{pem::PemLabel, SecretDocument},
+    pkcs5::pbes2,
+    EncryptedPrivateKeyInfo, LineEnding, PrivateKeyInfo,
+};
+use rand_core::{CryptoRng, RngCore};
+use std::convert::TryFrom;
+use zeroize::Zeroizing;
+
+use crate::{
+    audit::{audit, KeyOperation},
+    NodeIdentity,
+};
+
+/// PBKDF2 iterations used when encrypting keys, as recommended by OWASP for PBKDF2-HMAC-SHA256
+const PBKDF2_ITERATIONS: u32 = 600_000;
+
+impl NodeIdentity {
+    /// Parses a PEM encoded PKCS#8 private key, as written by yggdrasil-go v0.5.
+    ///
+    /// Requires the `pkcs8` feature.
+    pub fn from_pkcs8_pem(pem: &str) -> Result<Self, pkcs8::Error> {
+        let signing_keys = SigningKey::from_pkcs8_pem(pem)?;
+        Ok(Self { signing_keys })
+    }
+
+    /// PEM encode the secret key as PKCS#8, in the same format as yggdrasil-go v0.5.
+    ///
+    /// Requires the `pkcs8` feature.
+    pub fn to_pkcs8_pem(&self) -> Result<Zeroizing<String>, pkcs8::Error> {
+        audit(self, KeyOperation::Export, "NodeIdentity::to_pkcs8_pem");
+        self.pkcs8_pem()
+    }
+
+    /// Like [`NodeIdentity::to_pkcs8_pem`], without reporting to the audit hook.
+    pub(crate) fn pkcs8_pem(&self) -> Result<Zeroizing<String>, pkcs8::Error> {
+        self.keypair_bytes().to_pkcs8_pem(LineEnding::LF)
+    }
+
+    /// Parses a PEM encoded, password protected PKCS#8 private key
+    /// (`EncryptedPrivateKeyInfo`).
+    ///
+    /// Requires the `pkcs8` feature.
+    pub fn from_encrypted_pkcs8_pem(
+        pem: &str,
+        password: impl AsRef<[u8]>,
+    ) -> Result<Self, pkcs8::Error> {
+        let signing_keys = SigningKey::from_pkcs8_encrypted_pem(pem, password)?;
+        Ok(Self { signing_keys })
+    }
+
+    /// PEM encode the secret key as password protected PKCS#8 (`EncryptedPrivateKeyInfo`).
+    ///
+    /// The key is encrypted with AES-256-CBC, using a key derived from the password with
+    /// PBKDF2-HMAC-SHA256, which is what OpenSSL uses by default as well. The supplied CSPRNG is
+    /// used to generate the salt and IV.
+    ///
+    /// Requires the `pkcs8` feature.
+    pub fn to_encrypted_pkcs8_pem<R: CryptoRng + RngCore>(
+        &self,
+        csprng: &mut R,
+        password: impl AsRef<[u8]>,
+    ) -> Result<Zeroizing<String>, pkcs8::Error> {
+        audit(
+            self,
+            KeyOperation::Encrypt,
+            "NodeIdentity::to_encrypted_pkcs8_pem",
+        );
+        let encrypted = self.to_encrypted_pkcs8_der(csprng, password)?;
+        Ok(encrypted.to_pem(EncryptedPrivateKeyInfo::PEM_LABEL, LineEnding::LF)?)
+    }
+
+    /// DER encode the secret key as password protected PKCS#8,
+    /// like [`NodeIdentity::to_encrypted_pkcs8_pem`].
+    pub(crate) fn to_encrypted_pkcs8_der<R: CryptoRng + RngCore>(
+        &self,
+        csprng: &mut R,
+        password: impl AsRef<[u8]>,
+    ) -> Result<SecretDocument, pkcs8::Error> {
+        let mut salt = [0u8; 16];
+        let mut iv = [0u8; 16];
+        csprng.fill_bytes(&mut salt);
+        csprng.fill_bytes(&mut iv);
+        let params = pbes2::Parameters::pbkdf2_sha256_aes256cbc(PBKDF2_ITERATIONS, &salt, &iv)?;
+        let document = self.keypair_bytes().to_pkcs8_der()?;
+        PrivateKeyInfo::try_from(document.as_bytes())?.encrypt_with_params(params, password)
+    }
+
+    /// The secret key without the public key, encoded as PKCS#8 v1 like yggdrasil-go does
+    fn keypair_bytes(&self) -> KeypairBytes {
+        KeypairBytes {
+            secret_key: self.signing_keys.to_bytes(),
+            public_key: None,
+        }
+    }
+}
@@ -0,0 +1,181 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use rand_core::OsRng;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::{Miner, NodeIdentity, StepResult};
+
+/// How long workers mine before checking whether the pool is shutting down
+const STEP_BUDGET: Duration = Duration::from_millis(50);
+
+/// A pool of identities with a minimum strength, mined in the background.
+///
+/// Worker threads keep the pool filled up to its capacity, so identities can be handed out
+/// without mining on the request path. The workers are stopped when the pool is dropped.
+///
+/// ```rust
+/// use yggdrasil_keys::{KeyPool, NodeIdentity};
+///
+/// async fn provision(pool: &KeyPool) -> NodeIdentity {
+///     pool.acquire().await
+/// }
+///
+/// let pool = KeyPool::new(8, 16, 2);
+/// # drop(pool);
+/// ```
+pub struct KeyPool {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    space: Condvar,
+    shutdown: AtomicBool,
+    capacity: usize,
+}
+
+struct State {
+    identities: VecDeque<NodeIdentity>,
+    wakers: Vec<Waker>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // The state stays consistent even if a thread panicked while holding the lock
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl KeyPool {
+    /// Start mining identities with at least the given strength, keeping up to `capacity`
+    /// of them around, using the given number of worker threads.
+    pub fn new(min_strength: u32, capacity: usize, workers: usize) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                identities: VecDeque::with_capacity(capacity),
+                wakers: Vec::new(),
+            }),
+            space: Condvar::new(),
+            shutdown: AtomicBool::new(false),
+            capacity: capacity.max(1),
+        });
+        let workers = (0..workers.max(1))
+            .map(|_| {
+                let shared = Arc::clone(&shared);
+                std::thread::spawn(move || work(&shared, min_strength))
+            })
+            .collect();
+        Self { shared, workers }
+    }
+
+    /// Take an identity from the pool, waiting for one to be mined if the pool is empty.
+    pub fn acquire(&self) -> Acquire<'_> {
+        Acquire { pool: self }
+    }
+
+    /// Take an identity from the pool, if one is available right now.
+    pub fn try_acquire(&self) -> Option<NodeIdentity> {
+        let identity = self.shared.lock().identities.pop_front();
+        if identity.is_some() {
+            self.shared.space.notify_one();
+        }
+        identity
+    }
+
+    /// The number of identities ready to be handed out
+    pub fn len(&self) -> usize {
+        self.shared.lock().identities.len()
+    }
+
+    /// Whether no identities are ready to be handed out
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for KeyPool {
+    fn drop(&mut self) {
+        // Set the flag while holding the lock, so a worker can't check it and then start
+        // waiting for space after the notification below
+        let state = self.shared.lock();
+        self.shared.shutdown.store(true, Ordering::Relaxed);
+        drop(state);
+        self.shared.space.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// The future returned by [`KeyPool::acquire`].
+pub struct Acquire<'a> {
+    pool: &'a KeyPool,
+}
+
+impl Future for Acquire<'_> {
+    type Output = NodeIdentity;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<NodeIdentity> {
+        let mut state = self.pool.shared.lock();
+        match state.identities.pop_front() {
+            Some(identity) => {
+                drop(state);
+                self.pool.shared.space.notify_one();
+                Poll::Ready(identity)
+            }
+            None => {
+                if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    state.wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+fn work(shared: &Shared, min_strength: u32) {
+    let mut miner = Miner::new(min_strength);
+    while !shared.shutdown.load(Ordering::Relaxed) {
+        if let StepResult::Found(identity) = miner.step(&mut OsRng, STEP_BUDGET) {
+            // Hold on to the identity until there is space in the pool
+            let mut state = shared
+                .space
+                .wait_while(shared.lock(), |state| {
+                    state.identities.len() >= shared.capacity
+                        && !shared.shutdown.load(Ordering::Relaxed)
+                })
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            state.identities.push_back(identity);
+            for waker in state.wakers.drain(..) {
+                waker.wake();
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+//! The main types of this crate, and the traits needed to sign and verify with node keys.
+//!
+//! ```rust
+//! use rand::thread_rng;
+//! use yggdrasil_keys::prelude::*;
+//!
+//! let node = NodeIdentity::new(&mut thread_rng());
+//! let signature = node.sign(b"hello");
+//! assert!(node.to_public().verify(b"hello", &signature).is_ok());
+//! ```
+
+pub use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+#[cfg(feature = "ipnet")]
+pub use ipnet::Ipv6Net;
+
+#[cfg(feature = "ipnet")]
+pub use crate::NodeSubnet;
+#[cfg(feature = "hex")]
+pub use crate::PublicKeyPolicy;
+pub use crate::{
+    verify_signature, AddressVersion, ErrorCode, NodeAddress, NodeIdentity, VerificationMode,
+};
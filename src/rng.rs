@@ -0,0 +1,50 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use rand_core::{CryptoRng, Error, RngCore};
+
+/// Adapts a CSPRNG implementing the `rand_core` 0.9 traits, as used by `rand` 0.9, to the
+/// `rand_core` 0.6 traits taken by this crate.
+///
+/// Everything accepting a CSPRNG, like [`NodeIdentity::new`](crate::NodeIdentity::new), can be
+/// used with such RNGs by wrapping them, e.g. `NodeIdentity::new(&mut RandCore09(rand::rng()))`.
+/// Requires the `rand_core_0_9` feature.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RandCore09<R>(pub R);
+
+impl<R: rand_core_0_9::CryptoRng> RngCore for RandCore09<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.0.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<R: rand_core_0_9::CryptoRng> CryptoRng for RandCore09<R> {}
@@ -0,0 +1,98 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use core::{fmt, ops::Deref, str};
+
+use ed25519_dalek::VerifyingKey;
+use sha2::{Digest, Sha512};
+
+use crate::NodeIdentity;
+
+/// Domain separation prefix for the hashes [`ShortId`]s are taken from
+const SHORT_ID_CONTEXT: &[u8] = b"yggdrasil-keys short id v1";
+/// The lowercase RFC 4648 base32 alphabet
+const BASE32: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// A short, stable identifier for a node key, for labelling nodes in dashboards and logs.
+///
+/// The identifier consists of the first 65 bits of the SHA-512 hash of the context
+/// `yggdrasil-keys short id v1` and the public key, encoded as 13 lowercase base32 characters.
+/// Collisions become likely only at around four billion nodes, but short IDs can be brute forced,
+/// so they must not be used for authentication.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{NodeIdentity, ShortId};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let id = node.short_id();
+/// assert_eq!(id.len(), ShortId::LEN);
+/// assert_eq!(id, ShortId::new(&node.public_key()));
+/// println!("node {id} is up");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ShortId([u8; ShortId::LEN]);
+
+impl ShortId {
+    /// The length of short IDs in characters
+    pub const LEN: usize = 13;
+
+    /// Derive the short ID of a public key.
+    pub fn new(public_key: &VerifyingKey) -> Self {
+        let hash = Sha512::new()
+            .chain_update(SHORT_ID_CONTEXT)
+            .chain_update(public_key.as_bytes())
+            .finalize();
+        let mut bits = [0u8; 16];
+        bits.copy_from_slice(&hash[0..16]);
+        let bits = u128::from_be_bytes(bits);
+        let mut id = [0u8; Self::LEN];
+        for (index, char) in id.iter_mut().enumerate() {
+            *char = BASE32[((bits >> (123 - 5 * index)) & 0x1f) as usize];
+        }
+        Self(id)
+    }
+
+    /// The short ID as a string
+    pub fn as_str(&self) -> &str {
+        // Only contains characters from the base32 alphabet
+        str::from_utf8(&self.0).expect("short IDs are ASCII")
+    }
+}
+
+impl Deref for ShortId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for ShortId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl NodeIdentity {
+    /// Derive a short, stable identifier for the node key, see [`ShortId`].
+    pub fn short_id(&self) -> ShortId {
+        ShortId::new(&self.signing_keys.verifying_key())
+    }
+}
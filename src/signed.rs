@@ -0,0 +1,120 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::{Signature, Signer, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audit::{audit, KeyOperation},
+    helper::sorted_json,
+    verify_signature, NodeIdentity, SignedError, VerificationMode,
+};
+
+/// Domain separation prefix for the signatures in [`Signed`] payloads
+const SIGNED_CONTEXT: &[u8] = b"yggdrasil-keys signed payload v1";
+
+/// A payload signed by a node key.
+///
+/// The payload is serialized into canonical JSON (object keys sorted, no whitespace) before
+/// signing, so the signature doesn't depend on the order in which fields happen to be serialized.
+/// The signed message is the canonical JSON prefixed with `yggdrasil-keys signed payload v1`, so
+/// the signatures can't be mistaken for Matrix-style JSON signatures made with the same key.
+/// The envelope itself can be (de)serialized using serde, with the public key and signature
+/// encoded as hex strings.
+///
+/// Verifying only proves that the payload was signed by [`Signed::signer`], so callers need to
+/// check that the signer is the one they expect.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{NodeIdentity, Signed};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let signed = Signed::new(&node, String::from("hello")).unwrap();
+/// assert_eq!(signed.verify().unwrap(), "hello");
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Signed<T> {
+    payload: T,
+    #[serde(with = "crate::helper::serde_hex")]
+    signer: [u8; 32],
+    #[serde(with = "crate::helper::serde_hex")]
+    signature: [u8; 64],
+}
+
+impl<T: Serialize> Signed<T> {
+    /// Sign the payload with the node key.
+    pub fn new(identity: &NodeIdentity, payload: T) -> Result<Self, SignedError> {
+        let message = signed_message(&payload)?;
+        audit(identity, KeyOperation::Sign, "Signed::new");
+        let signature = identity.signing_keys.sign(&message);
+        Ok(Self {
+            payload,
+            signer: *identity.signing_keys.verifying_key().as_bytes(),
+            signature: signature.to_bytes(),
+        })
+    }
+
+    /// Verify the signature and return the payload.
+    pub fn verify(&self) -> Result<&T, SignedError> {
+        self.verify_with(VerificationMode::default())
+    }
+
+    /// Verify the signature with the given verification semantics and return the payload.
+    pub fn verify_with(&self, mode: VerificationMode) -> Result<&T, SignedError> {
+        let message = signed_message(&self.payload)?;
+        let public_key = VerifyingKey::from_bytes(&self.signer)?;
+        verify_signature(
+            &public_key,
+            &message,
+            &Signature::from_bytes(&self.signature),
+            mode,
+        )?;
+        Ok(&self.payload)
+    }
+
+    /// Verify the signature and unwrap the payload.
+    pub fn into_verified(self) -> Result<T, SignedError> {
+        self.into_verified_with(VerificationMode::default())
+    }
+
+    /// Verify the signature with the given verification semantics and unwrap the payload.
+    pub fn into_verified_with(self, mode: VerificationMode) -> Result<T, SignedError> {
+        self.verify_with(mode)?;
+        Ok(self.payload)
+    }
+}
+
+impl<T> Signed<T> {
+    /// The ed25519 public key of the node that signed the payload.
+    pub fn signer(&self) -> &[u8; 32] {
+        &self.signer
+    }
+
+    /// Access the payload without verifying the signature.
+    pub fn payload_unverified(&self) -> &T {
+        &self.payload
+    }
+}
+
+/// Serialize into JSON with sorted object keys and without whitespace, after the context prefix.
+fn signed_message<T: Serialize>(payload: &T) -> Result<Vec<u8>, serde_json::Error> {
+    let value = serde_json::to_value(payload)?;
+    Ok([SIGNED_CONTEXT, sorted_json(&value).as_bytes()].concat())
+}
@@ -0,0 +1,91 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use curve25519_dalek::{edwards::CompressedEdwardsY, Scalar};
+use ed25519_dalek::{Signature, SignatureError, Verifier, VerifyingKey};
+
+use crate::SignatureEncodingError;
+
+/// The ed25519 verification semantics to use when checking signatures.
+///
+/// Implementations of ed25519 disagree on edge cases, like signatures made with small order
+/// public keys, so deployments that need signatures to be accepted or rejected consistently with
+/// other implementations have to pick the semantics explicitly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum VerificationMode {
+    /// The default verification of ed25519-dalek, as described in RFC 8032. Non-canonical
+    /// signature scalars are rejected, but small order public keys and `R` values are accepted.
+    #[default]
+    Standard,
+    /// Like [`VerificationMode::Standard`], but additionally rejects small order public keys and
+    /// `R` values, which could otherwise be used to create signatures that are valid for
+    /// several messages. This is ed25519-dalek's `verify_strict`.
+    Strict,
+}
+
+/// Verify a signature over a message with the given verification semantics.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{verify_signature, NodeIdentity, VerificationMode};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let signature = node.sign(b"message");
+/// let public_key = node.public_key();
+/// assert!(verify_signature(&public_key, b"message", &signature, VerificationMode::Strict).is_ok());
+/// ```
+pub fn verify_signature(
+    public_key: &VerifyingKey,
+    message: &[u8],
+    signature: &Signature,
+    mode: VerificationMode,
+) -> Result<(), SignatureError> {
+    match mode {
+        VerificationMode::Standard => public_key.verify(message, signature),
+        VerificationMode::Strict => public_key.verify_strict(message, signature),
+    }
+}
+
+/// Check that a signature is encoded canonically and is not trivially malleable.
+///
+/// Signature verification only checks that a signature is valid, not that it is the only valid
+/// encoding of that signature. Applications that deduplicate or otherwise identify messages by
+/// their signature can use this to reject signatures that could have been altered by a third
+/// party without invalidating them. This checks that:
+///  - `R` is a canonically encoded curve point,
+///  - `R` is not of small order,
+///  - `S` is reduced modulo the group order.
+///
+/// This does not verify the signature itself.
+pub fn check_signature_encoding(signature: &Signature) -> Result<(), SignatureEncodingError> {
+    let r_bytes = signature.r_bytes();
+    let r = CompressedEdwardsY(*r_bytes)
+        .decompress()
+        .ok_or(SignatureEncodingError::InvalidR)?;
+    if r.compress().as_bytes() != r_bytes {
+        return Err(SignatureEncodingError::NonCanonicalR);
+    }
+    if r.is_small_order() {
+        return Err(SignatureEncodingError::SmallOrderR);
+    }
+    if bool::from(Scalar::from_canonical_bytes(*signature.s_bytes()).is_none()) {
+        return Err(SignatureEncodingError::NonCanonicalS);
+    }
+    Ok(())
+}
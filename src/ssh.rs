@@ -0,0 +1,117 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ed25519_dalek::VerifyingKey;
+use ssh_key::{
+    certificate::{Builder, CertType},
+    private::Ed25519Keypair,
+    public::Ed25519PublicKey,
+    Certificate, HashAlg, PrivateKey, PublicKey,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    audit::{audit, KeyOperation},
+    NodeIdentity, SshCertificateError,
+};
+
+impl NodeIdentity {
+    /// The node key as an OpenSSH public key, for use as a certificate authority.
+    ///
+    /// Its [`PublicKey::to_openssh`] encoding can be used in `TrustedUserCAKeys` for user
+    /// certificates, or in `known_hosts` with the `@cert-authority` marker for host certificates.
+    pub fn ssh_public_key(&self) -> PublicKey {
+        PublicKey::from(Ed25519PublicKey::from(self.signing_keys.verifying_key()))
+    }
+
+    /// Sign an OpenSSH user or host certificate with the node key.
+    ///
+    /// The builder holds the subject's public key, the validity window and everything else
+    /// that goes into the certificate.
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use ssh_key::certificate::{Builder, CertType};
+    /// use yggdrasil_keys::{verify_ssh_certificate_at, NodeIdentity};
+    ///
+    /// let ca = NodeIdentity::new(&mut thread_rng());
+    /// let host = NodeIdentity::new(&mut thread_rng());
+    ///
+    /// let mut builder =
+    ///     Builder::new_with_random_nonce(&mut thread_rng(), host.ssh_public_key(), 0, 100).unwrap();
+    /// builder.cert_type(CertType::Host).unwrap();
+    /// builder.valid_principal("host.example").unwrap();
+    /// let certificate = ca.sign_ssh_certificate(builder).unwrap();
+    ///
+    /// let ca_key = ca.public_key();
+    /// verify_ssh_certificate_at(&certificate, &ca_key, CertType::Host, "host.example", 50)
+    ///     .unwrap();
+    /// ```
+    pub fn sign_ssh_certificate(
+        &self,
+        builder: Builder,
+    ) -> Result<Certificate, SshCertificateError> {
+        audit(
+            self,
+            KeyOperation::Sign,
+            "NodeIdentity::sign_ssh_certificate",
+        );
+        let private_key = PrivateKey::from(Ed25519Keypair::from(&self.signing_keys));
+        Ok(builder.sign(&private_key)?)
+    }
+}
+
+/// Verify that a certificate was issued by the node key and is currently valid for the principal.
+///
+/// The certificate has to be signed by the node key, be of the given type, and list the
+/// principal or no principals at all. Critical options aren't interpreted, so they have to be
+/// checked by the caller.
+pub fn verify_ssh_certificate(
+    certificate: &Certificate,
+    ca: &VerifyingKey,
+    cert_type: CertType,
+    principal: &str,
+) -> Result<(), SshCertificateError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    verify_ssh_certificate_at(certificate, ca, cert_type, principal, now)
+}
+
+/// Verify a certificate like [`verify_ssh_certificate`], at the given unix timestamp.
+pub fn verify_ssh_certificate_at(
+    certificate: &Certificate,
+    ca: &VerifyingKey,
+    cert_type: CertType,
+    principal: &str,
+    timestamp: u64,
+) -> Result<(), SshCertificateError> {
+    let fingerprint = PublicKey::from(Ed25519PublicKey::from(ca)).fingerprint(HashAlg::Sha256);
+    certificate
+        .validate_at(timestamp, [&fingerprint])
+        .map_err(|_| SshCertificateError::InvalidCertificate)?;
+    if certificate.cert_type() != cert_type {
+        return Err(SshCertificateError::WrongCertificateType);
+    }
+    let principals = certificate.valid_principals();
+    if !principals.is_empty() && !principals.iter().any(|valid| valid == principal) {
+        return Err(SshCertificateError::WrongPrincipal(principal.to_owned()));
+    }
+    Ok(())
+}
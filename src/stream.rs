@@ -0,0 +1,85 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use std::io::{self, Read};
+
+use ed25519_dalek::{Signature, VerifyingKey};
+use sha2::{Digest, Sha512};
+
+use crate::{
+    audit::{audit, KeyOperation},
+    NodeIdentity, PublicNodeIdentity, StreamError, VerificationMode,
+};
+
+/// Context for the Ed25519ph signatures over streams, so they can't be confused with signatures
+/// made by other tools using the same key.
+const STREAM_CONTEXT: &[u8] = b"yggdrasil-keys stream v1";
+
+/// Hash everything the reader returns.
+fn hash_reader<R: Read + ?Sized>(reader: &mut R) -> io::Result<Sha512> {
+    let mut hasher = Sha512::new();
+    io::copy(reader, &mut hasher)?;
+    Ok(hasher)
+}
+
+impl NodeIdentity {
+    /// Sign everything the reader returns, without loading it into memory.
+    ///
+    /// The input is hashed incrementally and signed with Ed25519ph (RFC 8032) using the context
+    /// `yggdrasil-keys stream v1`. Such signatures are not interchangeable with signatures over
+    /// the same bytes made with [`ed25519_dalek::Signer`], and have to be checked with
+    /// [`verify_reader`].
+    ///
+    /// ```rust
+    /// use rand::thread_rng;
+    /// use yggdrasil_keys::{verify_reader, NodeIdentity, VerificationMode};
+    ///
+    /// let node = NodeIdentity::new(&mut thread_rng());
+    /// let image = vec![0x42; 1 << 20];
+    /// let signature = node.sign_reader(&mut image.as_slice()).unwrap();
+    /// let public_key = node.public_key();
+    /// verify_reader(&public_key, &mut image.as_slice(), &signature, VerificationMode::Strict)
+    ///     .unwrap();
+    /// ```
+    pub fn sign_reader<R: Read + ?Sized>(&self, reader: &mut R) -> io::Result<Signature> {
+        let hasher = hash_reader(reader)?;
+        audit(self, KeyOperation::Sign, "NodeIdentity::sign_reader");
+        Ok(self
+            .signing_keys
+            .sign_prehashed(hasher, Some(STREAM_CONTEXT))
+            .expect("the context is shorter than 256 bytes"))
+    }
+}
+
+/// Verify a signature made with [`NodeIdentity::sign_reader`] over everything the reader returns.
+pub fn verify_reader<R: Read + ?Sized>(
+    public_key: &VerifyingKey,
+    reader: &mut R,
+    signature: &Signature,
+    mode: VerificationMode,
+) -> Result<(), StreamError> {
+    let hasher = hash_reader(reader)?;
+    PublicNodeIdentity::from(*public_key).verify_prehashed_with(
+        hasher,
+        Some(STREAM_CONTEXT),
+        signature,
+        mode,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,70 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use ipnet::Ipv6Net;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::net::Ipv6Addr;
+
+use crate::{NodeIdentity, PublicNodeIdentity};
+
+/// The public facts about a node identity, for rendering or emitting them in one go.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::NodeIdentity;
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let summary = node.summary();
+/// assert_eq!(summary.address, node.address());
+/// println!("{} {} {} {}", summary.public_key, summary.address, summary.subnet, summary.strength);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IdentitySummary {
+    /// The hex encoded public key
+    pub public_key: String,
+    /// The address, with the default IP prefix
+    pub address: Ipv6Addr,
+    /// The routed `/64` subnet, with the default IP prefix
+    pub subnet: Ipv6Net,
+    /// The strength of the key, see [`PublicNodeIdentity::strength`]
+    pub strength: u32,
+}
+
+impl PublicNodeIdentity {
+    /// Collect the public key, address, subnet and strength of this identity.
+    pub fn summary(&self) -> IdentitySummary {
+        IdentitySummary {
+            public_key: self.to_hex(),
+            address: self.address(),
+            subnet: self.subnet(),
+            strength: self.strength(),
+        }
+    }
+}
+
+impl NodeIdentity {
+    /// Collect the public key, address, subnet and strength of this identity.
+    ///
+    /// This contains no secrets.
+    pub fn summary(&self) -> IdentitySummary {
+        self.to_public().summary()
+    }
+}
@@ -0,0 +1,68 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use rayon::prelude::*;
+use std::net::Ipv6Addr;
+
+use crate::{helper::address_bytes, NodeIdentity};
+
+/// The result of checking a claimed address against a public key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// The claimed address is the one derived from the public key.
+    Consistent,
+    /// The claimed address differs from the one derived from the public key.
+    Inconsistent {
+        /// The address actually derived from the public key.
+        expected: Ipv6Addr,
+    },
+}
+
+/// Check a list of claimed (address, public key) pairs for consistency.
+///
+/// The addresses are derived with the default IP prefix, in parallel using rayon. The returned
+/// verdicts are in the same order as the supplied pairs.
+///
+/// ```rust
+/// use rand::thread_rng;
+/// use yggdrasil_keys::{verify_pairs, NodeIdentity, Verdict};
+///
+/// let node = NodeIdentity::new(&mut thread_rng());
+/// let public_key = *node.public_key().as_bytes();
+/// let verdicts = verify_pairs(vec![(node.address(), public_key)]);
+/// assert_eq!(verdicts, vec![Verdict::Consistent]);
+/// ```
+pub fn verify_pairs<I>(pairs: I) -> Vec<Verdict>
+where
+    I: IntoIterator<Item = (Ipv6Addr, [u8; 32])>,
+{
+    let pairs: Vec<(Ipv6Addr, [u8; 32])> = pairs.into_iter().collect();
+    pairs
+        .par_iter()
+        .map(|(address, public_key)| {
+            let expected =
+                Ipv6Addr::from(address_bytes(public_key, &NodeIdentity::IP_PREFIX, false));
+            if *address == expected {
+                Verdict::Consistent
+            } else {
+                Verdict::Inconsistent { expected }
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,110 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use base64ct::{Base64, Encoding};
+use curve25519_dalek::MontgomeryPoint;
+use hkdf::Hkdf;
+use sha2::Sha512;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::NodeIdentity;
+
+/// HKDF info string used to derive the yggdrasil node key from a master seed
+const YGGDRASIL_INFO: &[u8] = b"yggdrasil-keys v1 yggdrasil ed25519";
+/// HKDF info string used to derive the WireGuard key from a master seed
+const WIREGUARD_INFO: &[u8] = b"yggdrasil-keys v1 wireguard x25519";
+
+/// A WireGuard (X25519) keypair.
+///
+/// The private key is zeroized when the keypair is dropped.
+pub struct WireGuardKeypair {
+    private_key: [u8; 32],
+    public_key: [u8; 32],
+}
+
+impl WireGuardKeypair {
+    /// Create a keypair from a private key, clamping it the way WireGuard does.
+    pub fn from_private_key(mut private_key: [u8; 32]) -> Self {
+        private_key[0] &= 248;
+        private_key[31] &= 127;
+        private_key[31] |= 64;
+        let public_key = MontgomeryPoint::mul_base_clamped(private_key).to_bytes();
+        Self {
+            private_key,
+            public_key,
+        }
+    }
+
+    /// The private key
+    pub fn private_key(&self) -> &[u8; 32] {
+        &self.private_key
+    }
+
+    /// The public key
+    pub fn public_key(&self) -> &[u8; 32] {
+        &self.public_key
+    }
+
+    /// The private key, base64 encoded as used in WireGuard configs
+    pub fn private_key_base64(&self) -> Zeroizing<String> {
+        Zeroizing::new(Base64::encode_string(&self.private_key))
+    }
+
+    /// The public key, base64 encoded as used in WireGuard configs
+    pub fn public_key_base64(&self) -> String {
+        Base64::encode_string(&self.public_key)
+    }
+}
+
+impl Drop for WireGuardKeypair {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+    }
+}
+
+/// Derive a node identity and a WireGuard keypair from a single master seed.
+///
+/// Both keys are derived with HKDF-SHA512 from the seed, using different info strings for domain
+/// separation. Neither key reveals anything about the other one or the seed, but anyone who has
+/// the seed can recreate both, so it has to be kept as secret as the keys themselves.
+///
+/// Requires the `wireguard` feature.
+///
+/// ```rust
+/// use yggdrasil_keys::derive_from_seed;
+///
+/// let seed = [42u8; 32];
+/// let (identity, wireguard) = derive_from_seed(&seed);
+/// let (same_identity, same_wireguard) = derive_from_seed(&seed);
+/// assert_eq!(identity.to_hex_joined(), same_identity.to_hex_joined());
+/// assert_eq!(wireguard.public_key(), same_wireguard.public_key());
+/// ```
+pub fn derive_from_seed(seed: &[u8; 32]) -> (NodeIdentity, WireGuardKeypair) {
+    let hkdf = Hkdf::<Sha512>::new(None, seed);
+
+    let mut secret = Zeroizing::new([0u8; 32]);
+    hkdf.expand(YGGDRASIL_INFO, &mut secret[..]).unwrap();
+    let signing_keys = ed25519_dalek::SigningKey::from_bytes(&secret);
+
+    let mut private_key = Zeroizing::new([0u8; 32]);
+    hkdf.expand(WIREGUARD_INFO, &mut private_key[..]).unwrap();
+    let wireguard = WireGuardKeypair::from_private_key(*private_key);
+
+    (NodeIdentity { signing_keys }, wireguard)
+}
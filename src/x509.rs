@@ -0,0 +1,116 @@
+/********************************************************************************
+ *   yggdrasil-keys-rs, a library for handling yggdrasil keys in rust           *
+ *                                                                              *
+ *   Copyright (C) 2020-2021 Famedly GmbH                                       *
+ *   Copyright (C) 2024 Jan Christian Grünhage                                  *
+ *                                                                              *
+ *   This program is free software: you can redistribute it and/or modify       *
+ *   it under the terms of the GNU Affero General Public License as             *
+ *   published by the Free Software Foundation, either version 3 of the         *
+ *   License, or (at your option) any later version.                            *
+ *                                                                              *
+ *   This program is distributed in the hope that it will be useful,            *
+ *   but WITHOUT ANY WARRANTY; without even the implied warranty of             *
+ *   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the               *
+ *   GNU Affero General Public License for more details.                        *
+ *                                                                              *
+ *   You should have received a copy of the GNU Affero General Public License   *
+ *   along with this program.  If not, see <https://www.gnu.org/licenses/>.     *
+ ********************************************************************************/
+use core::convert::TryFrom;
+
+use ed25519_dalek::{Signature, VerifyingKey};
+use x509_parser::{
+    certificate::X509Certificate, oid_registry::OID_SIG_ED25519, pem::parse_x509_pem,
+    prelude::FromDer,
+};
+
+use crate::{verify_signature, CertificateError, VerificationMode};
+
+/// How to check the certificates public keys are extracted from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum CertificateCheck {
+    /// Require the certificate to be signed by its own key, as the TLS certificates of
+    /// yggdrasil nodes are
+    #[default]
+    SelfSigned,
+    /// Only extract the key, for certificates issued by someone else
+    KeyOnly,
+}
+
+/// Extract the ed25519 public key from a DER encoded X.509 certificate.
+///
+/// Yggdrasil nodes use self-signed certificates for their node key when peering over TLS, so
+/// this gives the node key of a peer from the certificate it presented.
+pub fn public_key_from_certificate_der(der: &[u8]) -> Result<VerifyingKey, CertificateError> {
+    public_key_from_certificate_der_with(der, CertificateCheck::default())
+}
+
+/// Extract the ed25519 public key from a DER encoded X.509 certificate,
+/// checking the certificate according to the given policy.
+pub fn public_key_from_certificate_der_with(
+    der: &[u8],
+    check: CertificateCheck,
+) -> Result<VerifyingKey, CertificateError> {
+    let (_, certificate) =
+        X509Certificate::from_der(der).map_err(|_| CertificateError::Malformed)?;
+    public_key(&certificate, check)
+}
+
+/// Extract the ed25519 public key from a PEM encoded X.509 certificate.
+///
+/// ```rust
+/// use yggdrasil_keys::public_key_from_certificate_pem;
+///
+/// let pem = include_str!("../testdata/node.crt");
+/// let public_key = public_key_from_certificate_pem(pem).unwrap();
+/// assert_eq!(
+///     hex::encode(public_key.as_bytes()),
+///     "00000305eb7f19cb4506f937494ea2ebcf58e346604c0cf76be5f67271fd9a97"
+/// );
+/// ```
+pub fn public_key_from_certificate_pem(pem: &str) -> Result<VerifyingKey, CertificateError> {
+    public_key_from_certificate_pem_with(pem, CertificateCheck::default())
+}
+
+/// Extract the ed25519 public key from a PEM encoded X.509 certificate,
+/// checking the certificate according to the given policy.
+pub fn public_key_from_certificate_pem_with(
+    pem: &str,
+    check: CertificateCheck,
+) -> Result<VerifyingKey, CertificateError> {
+    let (_, pem) = parse_x509_pem(pem.as_bytes()).map_err(|_| CertificateError::Malformed)?;
+    let certificate = pem.parse_x509().map_err(|_| CertificateError::Malformed)?;
+    public_key(&certificate, check)
+}
+
+fn public_key(
+    certificate: &X509Certificate<'_>,
+    check: CertificateCheck,
+) -> Result<VerifyingKey, CertificateError> {
+    let info = certificate.public_key();
+    if info.algorithm.algorithm != OID_SIG_ED25519 {
+        return Err(CertificateError::NotEd25519);
+    }
+    let public_key = <[u8; 32]>::try_from(&*info.subject_public_key.data)
+        .ok()
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+        .ok_or(CertificateError::InvalidKey)?;
+
+    if check == CertificateCheck::SelfSigned {
+        let signature = Signature::from_slice(&certificate.signature_value.data)
+            .map_err(|_| CertificateError::InvalidSelfSignature)?;
+        if certificate.signature_algorithm.algorithm != OID_SIG_ED25519
+            || verify_signature(
+                &public_key,
+                certificate.tbs_certificate.as_ref(),
+                &signature,
+                VerificationMode::Strict,
+            )
+            .is_err()
+        {
+            return Err(CertificateError::InvalidSelfSignature);
+        }
+    }
+    Ok(public_key)
+}